@@ -0,0 +1,77 @@
+use accesskit::{Node, NodeId};
+use criterion::{criterion_group, criterion_main, Criterion};
+use fltk::{prelude::*, *};
+use fltk_accesskit::{accessible, builder, AccessibilityContext, Accessible};
+
+const ROWS: i32 = 100;
+const COLS: i32 = 30;
+
+/// A data-entry screen with `ROWS * COLS` inputs.
+fn screen() -> (window::Window, Vec<input::Input>) {
+    let mut win = window::Window::default().with_size(COLS * 40, ROWS * 10);
+    let mut inputs = Vec::new();
+    for r in 0..ROWS {
+        for c in 0..COLS {
+            inputs.push(input::Input::new(c * 40, r * 10, 40, 10, None));
+        }
+    }
+    win.end();
+    win.show();
+    (win, inputs)
+}
+
+/// What every update used to cost: a full walk whose nodes were all sent.
+fn full_rebuild(root: &window::Window) -> Vec<(NodeId, Node)> {
+    fn walk(grp: &group::Group, out: &mut Vec<(NodeId, Node)>) -> Vec<NodeId> {
+        let mut ids = Vec::new();
+        for i in 0..grp.children() {
+            if let Some(child) = grp.child(i) {
+                let children = match child.as_group() {
+                    Some(g) => walk(&g, out),
+                    None => Vec::new(),
+                };
+                let nodes = accessible::nodes_for_widget(&child, &children);
+                if let Some((id, _)) = nodes.last() {
+                    ids.push(*id);
+                }
+                out.extend(nodes);
+            }
+        }
+        ids
+    }
+    let mut out = Vec::new();
+    let children = walk(&root.as_group().unwrap(), &mut out);
+    out.push(root.make_node(&children));
+    out
+}
+
+fn bench(c: &mut Criterion) {
+    let _app = app::App::default();
+    let (win, mut inputs) = screen();
+    let ac: AccessibilityContext = builder(win.clone()).attach();
+
+    let mut group = c.benchmark_group("tree_update");
+    group.bench_function("full_rebuild", |b| b.iter(|| full_rebuild(&win)));
+    group.bench_function("incremental_unchanged", |b| {
+        b.iter(|| {
+            let update = ac.tree_update();
+            assert!(update.nodes.is_empty());
+            update
+        })
+    });
+    let mut n = 0;
+    group.bench_function("incremental_one_edit", |b| {
+        b.iter(|| {
+            n += 1;
+            let i = n % inputs.len();
+            inputs[i].set_value(&n.to_string());
+            let update = ac.tree_update();
+            assert_eq!(update.nodes.len(), 1);
+            update
+        })
+    });
+    group.finish();
+}
+
+criterion_group!(benches, bench);
+criterion_main!(benches);
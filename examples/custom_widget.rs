@@ -1,8 +1,8 @@
 #![windows_subsystem = "windows"]
 
-use accesskit::{Action, Node, NodeId, Rect, Role};
+use accesskit::{Action, Node, NodeId, Role};
 use fltk::{enums::*, prelude::*, *};
-use fltk_accesskit::{builder, Accessible, AccessibleApp};
+use fltk_accesskit::{accessible, builder, node_id, Accessible, AccessibleApp};
 
 #[derive(Clone)]
 struct MyButton {
@@ -19,15 +19,10 @@ impl MyButton {
 
 impl Accessible for MyButton {
     fn make_node(&self, _children: &[NodeId]) -> (NodeId, Node) {
-        let node_id = NodeId(self.as_widget_ptr() as usize as u64);
+        let node_id = node_id(&self.f);
         let node = {
             let mut builder = Node::new(Role::Button);
-            builder.set_bounds(Rect {
-                x0: self.x() as f64,
-                y0: self.y() as f64,
-                x1: (self.w() + self.x()) as f64,
-                y1: (self.h() + self.y()) as f64,
-            });
+            builder.set_bounds(accessible::node_bounds(&self.f));
             builder.set_label(&*self.label());
             builder.add_action(Action::Focus);
             builder.add_action(Action::Click);
@@ -52,7 +47,11 @@ fn main() {
     w.end();
     w.show();
 
-    let ac = builder(w).attach();
+    // fltk only sees the underlying button::Button, so tell the walker about our impl
+    let ac = builder(w)
+        .register_widget(&*b1, Box::new(b1.clone()))
+        .register_widget(&*b2, Box::new(b2.clone()))
+        .attach();
 
     b1.set_callback(|_| println!("clicked 1"));
 
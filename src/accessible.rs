@@ -1,30 +1,102 @@
-use accesskit::{Action, Affine, Node, NodeId, Rect, Role, TextPosition, TextSelection, Toggled};
+use accesskit::{
+    Action, ActionData, ActionRequest, Affine, Node, NodeId, Rect, Role, ScrollUnit, TextPosition,
+    TextSelection, Toggled,
+};
 use fltk::{
     button, enums::*, frame, input, menu, output, prelude::*, text, utils, widget, window, *,
 };
+use std::rc::Rc;
+
+use crate::ids::{child_node_id, node_id};
+use crate::label::set_label;
+use crate::menus::MenuWalker;
+use crate::shortcut::set_shortcut;
+use crate::targets::Targets;
 
 pub trait Accessible {
     fn make_node(&self, children: &[NodeId]) -> (NodeId, Node);
+    /// Handle an action requested by an assistive technology. Return `false` to fall
+    /// back to the default handling: `Focus` takes focus and `Click` runs the callback.
+    fn perform_action(&self, _request: &ActionRequest) -> bool {
+        false
+    }
+}
+
+/// Where the coordinate space of the nodes below `w` starts, in the coordinates fltk
+/// gives `w` itself, and in those it gives `w`'s children. Windows and scrolled groups
+/// start their own space, other widgets share their parent's.
+fn own_space(w: &widget::Widget) -> Option<((i32, i32), (i32, i32))> {
+    if w.as_window().is_some() && w.parent().is_some() {
+        // Subwindows: their children are relative to them
+        return Some(((w.x(), w.y()), (0, 0)));
+    }
+    if w.as_window().is_some() {
+        return Some(((0, 0), (0, 0)));
+    }
+    // The content of a scrolled group starts at its scroll position, so that scrolling
+    // only changes the group's transform and not the bounds of everything in it.
+    let s = group::Scroll::from_dyn_widget(w)?;
+    let origin = (s.x() - s.xposition(), s.y() - s.yposition());
+    Some((origin, origin))
+}
+
+/// The origin of the space `w`'s node is expressed in, in the coordinates fltk gives `w`.
+fn parent_space(w: &widget::Widget) -> (i32, i32) {
+    let mut parent = w.parent();
+    while let Some(p) = parent {
+        if let Some((_, inner)) = own_space(&p.as_base_widget()) {
+            return inner;
+        }
+        parent = p.parent();
+    }
+    (0, 0)
+}
+
+/// The bounds of `w`'s node, relative to the closest window or scrolled group around it
+/// since that's what the node's parent, or the closest node above it, is relative to.
+/// Custom [`Accessible`] impls should use this for their nodes.
+pub fn node_bounds<W: WidgetExt>(w: &W) -> Rect {
+    let w = w.as_base_widget();
+    let (ox, oy) = match own_space(&w) {
+        // Windows and scrolled groups are in their own space, placed by their transform
+        Some(_) if w.as_window().is_some() => (w.x(), w.y()),
+        Some((outer, _)) => outer,
+        None => parent_space(&w),
+    };
+    let (x, y) = (w.x() - ox, w.y() - oy);
+    Rect {
+        x0: x as f64,
+        y0: y as f64,
+        x1: (x + w.w()) as f64,
+        y1: (y + w.h()) as f64,
+    }
+}
+
+/// The translation from the space of the nodes below `w` to the space of `w`'s parent,
+/// for subwindows and scrolled groups.
+fn node_transform(w: &widget::Widget) -> Option<Affine> {
+    if w.parent().is_none() {
+        return None;
+    }
+    let ((ox, oy), _) = own_space(w)?;
+    let (px, py) = parent_space(w);
+    Some(Affine::translate(((ox - px) as f64, (oy - py) as f64)))
 }
 
 fn node_widget_common(builder: &mut Node, wid: &impl WidgetExt, children: &[NodeId]) -> NodeId {
-    let node_id = NodeId(wid.as_widget_ptr() as usize as u64);
-    if wid.parent().is_some() && wid.as_window().is_none() {
-        builder.set_bounds(Rect {
-            x0: wid.x() as f64,
-            y0: wid.y() as f64,
-            x1: (wid.w() + wid.x()) as f64,
-            y1: (wid.h() + wid.y()) as f64,
-        });
-    } else {
-        builder.set_bounds(Rect {
-            x0: 0.0,
-            y0: 0.0,
-            x1: wid.w() as f64,
-            y1: wid.h() as f64,
-        });
+    let node_id = node_id(wid);
+    builder.set_bounds(node_bounds(wid));
+    if let Some(t) = node_transform(&wid.as_base_widget()) {
+        builder.set_transform(t);
+    }
+    set_label(builder, &wid.label());
+    if let Some(b) = button::Button::from_dyn_widget(&wid.as_base_widget()) {
+        set_shortcut(builder, b.shortcut().bits());
+    }
+    // Often the only explanation of an icon button
+    if let Some(tip) = wid.tooltip().filter(|t| !t.is_empty() && *t != wid.label()) {
+        builder.set_description(tip);
     }
-    builder.set_label(&*wid.label());
     if wid.trigger().contains(CallbackTrigger::Release) {
         builder.add_action(Action::Click);
     }
@@ -34,247 +106,453 @@ fn node_widget_common(builder: &mut Node, wid: &impl WidgetExt, children: &[Node
     node_id
 }
 
-/// Try to build an accessibility node for a given widget pointer.
-/// Returns None for unsupported widget types. Window nodes are handled separately.
-pub fn node_for_widget(w: &widget::Widget, children: &[NodeId]) -> Option<(NodeId, Node)> {
-    let ptr = w.as_widget_ptr();
-    macro_rules! try_type {
-        ($t:ty) => {
-            if utils::is_ptr_of::<$t>(ptr) {
-                let typed = unsafe { <$t>::from_widget_ptr(ptr as _) };
-                let (id, node) = typed.make_node(children);
-                return Some((id, node));
+fn parse_bool(s: &str) -> bool {
+    matches!(s.to_ascii_lowercase().as_str(), "1" | "true" | "on" | "yes")
+}
+
+/// Check/toggle buttons: `Click` flips the value before running the callback like a
+/// mouse click would, `SetValue` takes a boolean string or a number.
+fn toggle_action<B: ButtonExt + Clone>(b: &B, request: &ActionRequest) -> bool {
+    let mut b = b.clone();
+    match (request.action, &request.data) {
+        (Action::Click, _) => {
+            b.set_value(!b.value());
+            b.do_callback();
+            true
+        }
+        (Action::SetValue, Some(ActionData::Value(s))) => {
+            b.set_value(parse_bool(s));
+            true
+        }
+        (Action::SetValue, Some(ActionData::NumericValue(n))) => {
+            b.set_value(*n != 0.0);
+            true
+        }
+        _ => false,
+    }
+}
+
+/// Radio buttons: `Click` turns the other radio buttons of the group off, as fltk does.
+fn radio_action<B: ButtonExt + Clone>(b: &B, request: &ActionRequest) -> bool {
+    if request.action != Action::Click {
+        return false;
+    }
+    let mut b = b.clone();
+    if let Some(parent) = b.parent() {
+        for i in 0..parent.children() {
+            if let Some(c) = parent.child(i) {
+                let p = c.as_widget_ptr();
+                let is_radio = utils::is_ptr_of::<button::RadioButton>(p)
+                    || utils::is_ptr_of::<button::RadioRoundButton>(p)
+                    || utils::is_ptr_of::<button::RadioLightButton>(p);
+                if is_radio && p != b.as_widget_ptr() {
+                    if let Some(mut other) = button::Button::from_dyn_widget_ptr(p) {
+                        other.set_value(false);
+                    }
+                }
             }
-        };
+        }
     }
+    b.set_value(true);
+    b.do_callback();
+    true
+}
 
-    // Buttons (more specific first)
-    try_type!(button::RadioButton);
-    try_type!(button::RadioRoundButton);
-    try_type!(button::CheckButton);
-    try_type!(button::ToggleButton);
-    try_type!(button::Button);
+/// Text inputs: value changes, replacing the selection and moving the caret/selection.
+fn input_action<I: InputExt + Clone>(i: &I, request: &ActionRequest) -> bool {
+    let mut i = i.clone();
+    let editing = matches!(
+        request.action,
+        Action::SetValue | Action::ReplaceSelectedText
+    );
+    if editing && i.readonly() {
+        // Handled, by refusing: the default handling mustn't apply either
+        return true;
+    }
+    match (request.action, &request.data) {
+        (Action::SetValue, Some(ActionData::Value(s))) => {
+            i.set_value(s);
+            true
+        }
+        (Action::SetValue, Some(ActionData::NumericValue(n))) => {
+            i.set_value(&format!("{}", n));
+            true
+        }
+        (Action::ReplaceSelectedText, Some(ActionData::Value(s))) => {
+            let start = i.position();
+            let end = i.mark();
+            if start != end {
+                let _ = i.replace(start, end, s);
+                let _ = i.set_position(start + s.len() as i32);
+                let _ = i.set_mark(start + s.len() as i32);
+            } else {
+                let _ = i.insert(s);
+                let new_pos = start + s.len() as i32;
+                let _ = i.set_position(new_pos);
+                let _ = i.set_mark(new_pos);
+            }
+            true
+        }
+        (Action::SetTextSelection, Some(ActionData::SetTextSelection(sel))) => {
+            // Only apply when selection nodes target this widget
+            if sel.anchor.node != request.target || sel.focus.node != request.target {
+                return false;
+            }
+            let len = i.value().len() as i32;
+            let a = (sel.anchor.character_index as i32).clamp(0, len);
+            let f = (sel.focus.character_index as i32).clamp(0, len);
+            let (start, end) = if a <= f { (a, f) } else { (f, a) };
+            // Set selection; on collapse, mark==position
+            let _ = i.set_position(start);
+            let _ = i.set_mark(end);
+            true
+        }
+        _ => false,
+    }
+}
 
-    // Inputs/Outputs/Text
-    try_type!(input::IntInput);
-    try_type!(input::FloatInput);
-    try_type!(input::MultilineInput);
-    try_type!(input::Input);
-    try_type!(text::TextEditor);
-    try_type!(output::MultilineOutput);
-    try_type!(output::Output);
-    try_type!(text::TextDisplay);
+/// Valuators: `SetValue` (numeric or parsed from a string), `Increment` and `Decrement`
+/// by one step. The callback runs as it would for a user interaction.
+fn valuator_action<V: ValuatorExt + Clone>(v: &V, request: &ActionRequest) -> bool {
+    let mut v = v.clone();
+    let mut step = v.step();
+    if step == 0.0 {
+        step = (v.maximum() - v.minimum()).abs() / 100.0;
+    }
+    // fltk allows minimum > maximum (e.g. vertical sliders); Increment moves toward maximum
+    if v.maximum() < v.minimum() {
+        step = -step;
+    }
+    let value = match (request.action, &request.data) {
+        (Action::SetValue, Some(ActionData::NumericValue(n))) => *n,
+        (Action::SetValue, Some(ActionData::Value(s))) => match s.parse::<f64>() {
+            Ok(n) => n,
+            Err(_) => return false,
+        },
+        (Action::Increment, _) => v.value() + step,
+        (Action::Decrement, _) => v.value() - step,
+        _ => return false,
+    };
+    v.set_value(v.clamp(value));
+    v.do_callback();
+    true
+}
 
-    // Frames (image/label)
-    try_type!(frame::Frame);
+fn set_editor_text(e: &mut text::TextEditor, s: &str) {
+    if let Some(mut buf) = e.buffer() {
+        buf.set_text(s);
+    } else {
+        let mut buf = text::TextBuffer::default();
+        buf.set_text(s);
+        e.set_buffer(Some(buf));
+    }
+}
+
+type Downcast = fn(&widget::Widget) -> Option<Box<dyn Accessible>>;
+
+fn downcast<T: WidgetBase + Accessible + 'static>(
+    w: &widget::Widget,
+) -> Option<Box<dyn Accessible>> {
+    T::from_dyn_widget_ptr(w.as_widget_ptr()).map(|t| Box::new(t) as Box<dyn Accessible>)
+}
 
+/// Every built-in `Accessible` impl, most specific type first. The type checks follow
+/// the C++ class hierarchy, so a subclass must be listed before its base class
+/// (`Output` derives from `Input`, `Scrollbar` from `Slider`, every container from `Group`).
+#[allow(deprecated)]
+static BUILTINS: &[Downcast] = &[
+    // Buttons
+    downcast::<button::RadioRoundButton>,
+    downcast::<button::RadioButton>,
+    downcast::<button::CheckButton>,
+    downcast::<button::ToggleButton>,
+    downcast::<button::Button>,
+    // Outputs/Inputs
+    downcast::<output::MultilineOutput>,
+    downcast::<output::Output>,
+    downcast::<input::MultilineInput>,
+    downcast::<input::IntInput>,
+    downcast::<input::FloatInput>,
+    downcast::<input::Input>,
+    // Valuators
+    downcast::<valuator::Scrollbar>,
+    downcast::<valuator::HorValueSlider>,
+    downcast::<valuator::ValueSlider>,
+    downcast::<valuator::HorFillSlider>,
+    downcast::<valuator::FillSlider>,
+    downcast::<valuator::HorNiceSlider>,
+    downcast::<valuator::NiceSlider>,
+    downcast::<valuator::HorSlider>,
+    downcast::<valuator::Slider>,
+    downcast::<valuator::FillDial>,
+    downcast::<valuator::LineDial>,
+    downcast::<valuator::Dial>,
+    downcast::<valuator::Counter>,
+    downcast::<valuator::Roller>,
+    downcast::<valuator::ValueInput>,
+    downcast::<valuator::ValueOutput>,
+    downcast::<misc::Progress>,
+    downcast::<misc::Spinner>,
+    // Menus
+    downcast::<menu::MenuBar>,
+    downcast::<menu::MenuButton>,
+    downcast::<menu::Choice>,
+    // Frames (image/label)
+    downcast::<frame::Frame>,
+    // Browsers, which are groups too
+    downcast::<browser::CheckBrowser>,
+    downcast::<browser::Browser>,
+    // Groups: text widgets, tables and windows are all groups in fltk
+    downcast::<text::SimpleTerminal>,
+    downcast::<text::TextEditor>,
+    downcast::<text::TextDisplay>,
+    downcast::<table::Table>,
+    downcast::<tree::Tree>,
+    downcast::<group::Scroll>,
+    downcast::<group::Flex>,
     // Windows (non-root windows will be discovered)
-    try_type!(window::Window);
+    downcast::<window::Window>,
+    downcast::<group::Group>,
+];
 
-    None
+/// Find the built-in `Accessible` impl matching the widget's dynamic type.
+pub fn accessible_for_widget(w: &widget::Widget) -> Option<Box<dyn Accessible>> {
+    BUILTINS.iter().find_map(|f| f(w))
+}
+
+/// Try to build an accessibility node for a given widget pointer.
+/// Returns None for unsupported widget types. Window nodes are handled separately.
+pub fn node_for_widget(w: &widget::Widget, children: &[NodeId]) -> Option<(NodeId, Node)> {
+    let (id, mut node) = accessible_for_widget(w)?.make_node(children);
+    crate::props::apply(w, &mut node);
+    Some((id, node))
+}
+
+/// Route an action request to the widget's `Accessible` impl: the `custom` one first,
+/// then the built-in one, then the default handling.
+pub(crate) fn perform_action(
+    w: &widget::Widget,
+    custom: Option<Rc<dyn Accessible>>,
+    request: &ActionRequest,
+) {
+    if custom.is_some_and(|a| a.perform_action(request)) {
+        return;
+    }
+    if accessible_for_widget(w).is_some_and(|a| a.perform_action(request)) {
+        return;
+    }
+    let mut w = w.clone();
+    match request.action {
+        Action::Click => w.do_callback(),
+        Action::Focus => {
+            let _ = w.take_focus();
+        }
+        _ => {}
+    }
+}
+
+/// Whether a group's children are internal parts of the widget (scrollbars,
+/// embedded inputs and buttons) rather than content to be walked.
+pub(crate) fn is_composite(w: &widget::Widget) -> bool {
+    let ptr = w.as_widget_ptr();
+    utils::is_ptr_of::<text::TextDisplay>(ptr)
+        || utils::is_ptr_of::<table::Table>(ptr)
+        || utils::is_ptr_of::<tree::Tree>(ptr)
+        || utils::is_ptr_of::<browser::Browser>(ptr)
+        || utils::is_ptr_of::<browser::CheckBrowser>(ptr)
+        || utils::is_ptr_of::<misc::Spinner>(ptr)
+        || utils::is_ptr_of::<misc::InputChoice>(ptr)
+        || utils::is_ptr_of::<misc::HelpView>(ptr)
+        || utils::is_ptr_of::<group::ColorChooser>(ptr)
+        || utils::is_ptr_of::<terminal::Terminal>(ptr)
 }
 
 /// Build one or more nodes for a widget. Some complex widgets (menus, choices)
-/// expand to multiple nodes to expose their items.
-pub fn nodes_for_widget(w: &widget::Widget) -> Vec<(NodeId, Node)> {
+/// expand to multiple nodes to expose their items, and groups only there for layout
+/// get none. `children` are the ids of the widget's accessible descendants. The
+/// widget's own node is always the last entry.
+pub fn nodes_for_widget(w: &widget::Widget, children: &[NodeId]) -> Vec<(NodeId, Node)> {
+    let mut nodes = expand_widget(w, children, &mut Targets::default());
+    apply_state(w, &mut nodes);
+    nodes
+}
+
+/// Marks the nodes of `w`, its own node last, with the state every impl shares: disabled
+/// and without actions when `w` or a parent is inactive, read-only for read-only inputs.
+pub(crate) fn apply_state(w: &widget::Widget, nodes: &mut [(NodeId, Node)]) {
+    if !w.active_r() {
+        for (_, node) in nodes.iter_mut() {
+            node.set_disabled();
+            node.clear_actions();
+        }
+    }
+    // Outputs are read-only inputs
+    if let (Some(i), Some((_, node))) = (input::Input::from_dyn_widget(w), nodes.last_mut()) {
+        if i.readonly() {
+            node.set_read_only();
+            node.remove_action(Action::SetValue);
+            node.remove_action(Action::ReplaceSelectedText);
+        }
+    }
+}
+
+/// Whether `w` is a group only there for layout, like an unlabeled `Group`, `Pack`,
+/// `Tile` or `Flex`. Those get no node, their children are hoisted into their parent's.
+fn is_layout_only(w: &widget::Widget) -> bool {
+    let ptr = w.as_widget_ptr();
+    utils::is_ptr_of::<group::Group>(ptr)
+        && w.as_window().is_none()
+        && !utils::is_ptr_of::<group::Scroll>(ptr)
+        && !utils::is_ptr_of::<group::Tabs>(ptr)
+        && !is_composite(w)
+        // Tab panels are labelled by their tab
+        && !w
+            .parent()
+            .is_some_and(|p| utils::is_ptr_of::<group::Tabs>(p.as_widget_ptr()))
+        && w.label().is_empty()
+        && w.tooltip().is_none_or(|t| t.is_empty())
+        && !crate::props::any(w)
+}
+
+/// [`nodes_for_widget`], recording what each synthetic child node stands for.
+pub(crate) fn expand_widget(
+    w: &widget::Widget,
+    children: &[NodeId],
+    targets: &mut Targets,
+) -> Vec<(NodeId, Node)> {
+    if is_layout_only(w) {
+        return Vec::new();
+    }
+    let mut out = expand_builtin(w, children, targets);
+    if let Some((_, node)) = out.last_mut() {
+        crate::tabs::mark_panel(w, node);
+        // The properties set by the application win over what we derived
+        crate::props::apply(w, node);
+    }
+    out
+}
+
+fn expand_builtin(
+    w: &widget::Widget,
+    children: &[NodeId],
+    targets: &mut Targets,
+) -> Vec<(NodeId, Node)> {
     let mut out = Vec::new();
     let ptr = w.as_widget_ptr();
 
-    // Choice -> ComboBox with options
+    // Choice -> ComboBox with a popup ListBox of options
     if utils::is_ptr_of::<menu::Choice>(ptr) {
         let choice = unsafe { menu::Choice::from_widget_ptr(ptr as _) };
-        let mut parent = Node::new(Role::ComboBox);
-        if let Some(val) = choice.choice() {
-            parent.set_value(&*val);
-        }
-        parent.add_action(Action::Focus);
-        parent.add_action(Action::SetValue);
-        parent.set_has_popup(accesskit::HasPopup::Menu);
-        let parent_id = node_widget_common(&mut parent, &choice, &[]);
-
-        let total = choice.size();
-        for i in 0..choice.size() {
-            if let Some(item) = choice.at(i) {
-                let mut node = Node::new(Role::ListBoxOption);
-                if let Some(lbl) = item.label() {
-                    node.set_label(&*lbl);
-                }
-                if i == choice.value() {
-                    node.set_selected(true);
-                }
-                node.set_position_in_set((i + 1) as usize);
-                node.set_size_of_set(total as usize);
-                let item_id = NodeId(unsafe { item.as_ptr() } as usize as u64);
-                parent.push_child(item_id);
-                out.push((item_id, node));
-            }
+        let (parent_id, mut parent) = choice.make_node(&[]);
+        // Item addresses are never 0
+        let list_id = child_node_id(parent_id, 0);
+        let mut list = Node::new(Role::ListBox);
+        let options = MenuWalker {
+            menu: &choice,
+            owner: parent_id,
+            item_role: Role::ListBoxOption,
+            chosen: choice.mvalue().map(|it| unsafe { it.as_ptr() } as usize),
+        }
+        .walk(&mut out, targets);
+        for id in options {
+            list.push_child(id);
+        }
+        out.push((list_id, list));
+        parent.push_child(list_id);
+        parent.push_controlled(list_id);
+        // The focus stays on the choice while its popup is open
+        if let Some(item) = crate::popup::shown(w).and_then(|s| s.highlighted) {
+            parent.set_active_descendant(child_node_id(parent_id, item as u64));
         }
-
-        parent.set_size_of_set(total as usize);
         out.push((parent_id, parent));
         return out;
     }
 
-    // MenuBar -> MenuBar with menu/menuitems
-    if utils::is_ptr_of::<menu::MenuBar>(ptr) {
-        let bar = unsafe { menu::MenuBar::from_widget_ptr(ptr as _) };
-        let mut bar_node = Node::new(Role::MenuBar);
-        bar_node.add_action(Action::Focus);
-        let bar_id = node_widget_common(&mut bar_node, &bar, &[]);
-
-        for i in 0..bar.size() {
-            if let Some(item) = bar.at(i) {
-                if item.is_submenu() {
-                    // Submenu as Role::Menu
-                    let mut menu_node = Node::new(Role::Menu);
-                    if let Some(lbl) = item.label() {
-                        menu_node.set_label(&*lbl);
-                    }
-                    let menu_id = NodeId(unsafe { item.as_ptr() } as usize as u64);
-                    bar_node.push_child(menu_id);
-
-                    // Add submenu items
-                    let count = item.size();
-                    for j in 0..count {
-                        if let Some(sub) = item.at(j) {
-                            let mut sub_node = Node::new(Role::MenuItem);
-                            if let Some(lbl) = sub.label() {
-                                sub_node.set_label(&*lbl);
-                            }
-                            if (sub.is_radio() || sub.is_checkbox()) && sub.value() {
-                                sub_node.set_selected(true);
-                            }
-                            let sub_id = NodeId(unsafe { sub.as_ptr() } as usize as u64);
-                            menu_node.push_child(sub_id);
-                            out.push((sub_id, sub_node));
-                        }
-                    }
-                    out.push((menu_id, menu_node));
-                } else {
-                    // Top-level item
-                    let mut node = Node::new(Role::MenuItem);
-                    if let Some(lbl) = item.label() {
-                        node.set_label(&*lbl);
-                    }
-                    if (item.is_radio() || item.is_checkbox()) && item.value() {
-                        node.set_selected(true);
-                    }
-                    let item_id = NodeId(unsafe { item.as_ptr() } as usize as u64);
-                    bar_node.push_child(item_id);
-                    out.push((item_id, node));
-                }
+    // Browsers -> ListBox with an option per line
+    if utils::is_ptr_of::<browser::Browser>(ptr) || utils::is_ptr_of::<browser::CheckBrowser>(ptr) {
+        if let Some(a) = accessible_for_widget(w) {
+            let (id, mut node) = a.make_node(&[]);
+            for line in crate::browsers::line_nodes(w, id, &mut out, targets).unwrap_or_default() {
+                node.push_child(line);
             }
+            out.push((id, node));
         }
-        out.push((bar_id, bar_node));
         return out;
     }
 
-    // SysMenuBar -> MenuBar representation
-    if utils::is_ptr_of::<menu::SysMenuBar>(ptr) {
-        let bar = unsafe { menu::SysMenuBar::from_widget_ptr(ptr as _) };
+    // Tabs -> Group of a TabList with a Tab per child, and the shown child as a TabPanel
+    if utils::is_ptr_of::<group::Tabs>(ptr) {
+        let tabs = unsafe { group::Tabs::from_widget_ptr(ptr as _) };
+        let mut node = Node::new(Role::Group);
+        let id = node_widget_common(&mut node, &tabs, &[]);
+        // Tabs are clicked through their tabs
+        node.remove_action(Action::Click);
+        let list_id = crate::tabs::tab_list(&tabs, id, &mut out, targets);
+        node.push_child(list_id);
+        for c in children {
+            node.push_child(*c);
+        }
+        out.push((id, node));
+        return out;
+    }
+
+    // MenuBar (and SysMenuBar, which derives from it) -> MenuBar with menus/menuitems
+    if utils::is_ptr_of::<menu::MenuBar>(ptr) {
+        let bar = unsafe { menu::MenuBar::from_widget_ptr(ptr as _) };
         let mut bar_node = Node::new(Role::MenuBar);
         bar_node.add_action(Action::Focus);
         let bar_id = node_widget_common(&mut bar_node, &bar, &[]);
-
-        for i in 0..bar.size() {
-            if let Some(item) = bar.at(i) {
-                if item.is_submenu() {
-                    let mut menu_node = Node::new(Role::Menu);
-                    if let Some(lbl) = item.label() {
-                        menu_node.set_label(&*lbl);
-                    }
-                    let menu_id = NodeId(unsafe { item.as_ptr() } as usize as u64);
-                    bar_node.push_child(menu_id);
-                    let count = item.size();
-                    for j in 0..count {
-                        if let Some(sub) = item.at(j) {
-                            let mut sub_node = Node::new(Role::MenuItem);
-                            if let Some(lbl) = sub.label() {
-                                sub_node.set_label(&*lbl);
-                            }
-                            if (sub.is_radio() || sub.is_checkbox()) && sub.value() {
-                                sub_node.set_selected(true);
-                            }
-                            let sub_id = NodeId(unsafe { sub.as_ptr() } as usize as u64);
-                            menu_node.push_child(sub_id);
-                            out.push((sub_id, sub_node));
-                        }
-                    }
-                    out.push((menu_id, menu_node));
-                } else {
-                    let mut node = Node::new(Role::MenuItem);
-                    if let Some(lbl) = item.label() {
-                        node.set_label(&*lbl);
-                    }
-                    if (item.is_radio() || item.is_checkbox()) && item.value() {
-                        node.set_selected(true);
-                    }
-                    let item_id = NodeId(unsafe { item.as_ptr() } as usize as u64);
-                    bar_node.push_child(item_id);
-                    out.push((item_id, node));
-                }
-            }
+        let items = MenuWalker {
+            menu: &bar,
+            owner: bar_id,
+            item_role: Role::MenuItem,
+            chosen: None,
+        }
+        .walk(&mut out, targets);
+        for id in items {
+            bar_node.push_child(id);
         }
         out.push((bar_id, bar_node));
         return out;
     }
 
-    // MenuButton -> Button with popup menu items
+    // MenuButton -> Button with a popup Menu of items
     if utils::is_ptr_of::<menu::MenuButton>(ptr) {
         let btn = unsafe { menu::MenuButton::from_widget_ptr(ptr as _) };
         let mut btn_node = Node::new(Role::Button);
         btn_node.add_action(Action::Focus);
         btn_node.add_action(Action::Click);
         btn_node.set_has_popup(accesskit::HasPopup::Menu);
-        btn_node.set_label(&*btn.label());
+        let open = crate::popup::shown(w).is_some();
+        if !open {
+            btn_node.add_action(Action::Expand);
+        } else if crate::popup::can_close() {
+            btn_node.add_action(Action::Collapse);
+        }
+        btn_node.set_expanded(open);
         let btn_id = node_widget_common(&mut btn_node, &btn, &[]);
-
-        // Expose menu items as children
-        for i in 0..btn.size() {
-            if let Some(item) = btn.at(i) {
-                if item.is_submenu() {
-                    let mut menu_node = Node::new(Role::Menu);
-                    if let Some(lbl) = item.label() {
-                        menu_node.set_label(&*lbl);
-                    }
-                    let menu_id = NodeId(unsafe { item.as_ptr() } as usize as u64);
-                    btn_node.push_child(menu_id);
-                    let count = item.size();
-                    for j in 0..count {
-                        if let Some(sub) = item.at(j) {
-                            let mut sub_node = Node::new(Role::MenuItem);
-                            if let Some(lbl) = sub.label() {
-                                sub_node.set_label(&*lbl);
-                            }
-                            if (sub.is_radio() || sub.is_checkbox()) && sub.value() {
-                                sub_node.set_selected(true);
-                            }
-                            let sub_id = NodeId(unsafe { sub.as_ptr() } as usize as u64);
-                            menu_node.push_child(sub_id);
-                            out.push((sub_id, sub_node));
-                        }
-                    }
-                    out.push((menu_id, menu_node));
-                } else {
-                    let mut node = Node::new(Role::MenuItem);
-                    if let Some(lbl) = item.label() {
-                        node.set_label(&*lbl);
-                    }
-                    if (item.is_radio() || item.is_checkbox()) && item.value() {
-                        node.set_selected(true);
-                    }
-                    let item_id = NodeId(unsafe { item.as_ptr() } as usize as u64);
-                    btn_node.push_child(item_id);
-                    out.push((item_id, node));
-                }
-            }
+        // Item addresses are never 0
+        let menu_id = child_node_id(btn_id, 0);
+        let mut menu_node = Node::new(Role::Menu);
+        let items = MenuWalker {
+            menu: &btn,
+            owner: btn_id,
+            item_role: Role::MenuItem,
+            chosen: None,
         }
+        .walk(&mut out, targets);
+        for id in items {
+            menu_node.push_child(id);
+        }
+        out.push((menu_id, menu_node));
+        btn_node.push_child(menu_id);
+        btn_node.push_controlled(menu_id);
         out.push((btn_id, btn_node));
         return out;
     }
 
-    if let Some(n) = node_for_widget(w, &[]) {
-        out.push(n);
+    if let Some(a) = accessible_for_widget(w) {
+        out.push(a.make_node(children));
     }
     out
 }
@@ -300,6 +578,9 @@ impl Accessible for button::RadioButton {
         let id = node_widget_common(&mut builder, self, children);
         (id, builder)
     }
+    fn perform_action(&self, request: &ActionRequest) -> bool {
+        radio_action(self, request)
+    }
 }
 
 impl Accessible for button::RadioRoundButton {
@@ -314,6 +595,9 @@ impl Accessible for button::RadioRoundButton {
         let id = node_widget_common(&mut builder, self, children);
         (id, builder)
     }
+    fn perform_action(&self, request: &ActionRequest) -> bool {
+        radio_action(self, request)
+    }
 }
 
 impl Accessible for button::CheckButton {
@@ -328,6 +612,9 @@ impl Accessible for button::CheckButton {
         let id = node_widget_common(&mut builder, self, children);
         (id, builder)
     }
+    fn perform_action(&self, request: &ActionRequest) -> bool {
+        toggle_action(self, request)
+    }
 }
 
 impl Accessible for button::ToggleButton {
@@ -342,13 +629,19 @@ impl Accessible for button::ToggleButton {
         let id = node_widget_common(&mut builder, self, children);
         (id, builder)
     }
+    fn perform_action(&self, request: &ActionRequest) -> bool {
+        toggle_action(self, request)
+    }
 }
 
 impl Accessible for window::Window {
     fn make_node(&self, children: &[NodeId]) -> (NodeId, Node) {
         let mut builder = Node::new(Role::Window);
-        let sn = app::screen_num(self.x(), self.y());
-        builder.set_transform(Affine::scale(app::screen_scale(sn) as f64));
+        // Subwindows get the offset to their parent instead, they're already scaled
+        if self.parent().is_none() {
+            let sn = app::screen_num(self.x(), self.y());
+            builder.set_transform(Affine::scale(app::screen_scale(sn) as f64));
+        }
         let id = node_widget_common(&mut builder, self, children);
         (id, builder)
     }
@@ -385,6 +678,9 @@ impl Accessible for input::Input {
         let id = node_widget_common(&mut builder, self, children);
         (id, builder)
     }
+    fn perform_action(&self, request: &ActionRequest) -> bool {
+        input_action(self, request)
+    }
 }
 
 impl Accessible for input::IntInput {
@@ -396,6 +692,17 @@ impl Accessible for input::IntInput {
         let id = node_widget_common(&mut builder, self, children);
         (id, builder)
     }
+    fn perform_action(&self, request: &ActionRequest) -> bool {
+        if let (Action::SetValue, Some(ActionData::NumericValue(n))) =
+            (request.action, &request.data)
+        {
+            if !self.readonly() {
+                self.clone().set_value(&format!("{}", n.round() as i64));
+            }
+            return true;
+        }
+        input_action(self, request)
+    }
 }
 
 impl Accessible for input::FloatInput {
@@ -407,6 +714,9 @@ impl Accessible for input::FloatInput {
         let id = node_widget_common(&mut builder, self, children);
         (id, builder)
     }
+    fn perform_action(&self, request: &ActionRequest) -> bool {
+        input_action(self, request)
+    }
 }
 
 impl Accessible for input::MultilineInput {
@@ -418,6 +728,9 @@ impl Accessible for input::MultilineInput {
         let id = node_widget_common(&mut builder, self, children);
         (id, builder)
     }
+    fn perform_action(&self, request: &ActionRequest) -> bool {
+        input_action(self, request)
+    }
 }
 
 impl Accessible for output::MultilineOutput {
@@ -480,6 +793,67 @@ impl Accessible for text::TextEditor {
         }
         (id, builder)
     }
+    fn perform_action(&self, request: &ActionRequest) -> bool {
+        let mut e = self.clone();
+        match (request.action, &request.data) {
+            (Action::SetValue, Some(ActionData::Value(s))) => {
+                set_editor_text(&mut e, s);
+                true
+            }
+            (Action::SetValue, Some(ActionData::NumericValue(n))) => {
+                set_editor_text(&mut e, &format!("{}", n));
+                true
+            }
+            (Action::ReplaceSelectedText, Some(ActionData::Value(s))) => {
+                if let Some(mut buf) = e.buffer() {
+                    match buf.selection_position() {
+                        Some((start, end)) if start != end => {
+                            buf.replace(start, end, s);
+                            e.set_insert_position(start + s.len() as i32);
+                        }
+                        _ => {
+                            let pos = e.insert_position();
+                            buf.insert(pos, s);
+                            e.set_insert_position(pos + s.len() as i32);
+                        }
+                    }
+                }
+                true
+            }
+            (Action::ScrollIntoView, _) => {
+                // Best effort: ensure caret is visible
+                e.show_insert_position();
+                true
+            }
+            (Action::SetTextSelection, Some(ActionData::SetTextSelection(sel))) => {
+                // Only apply when selection nodes target this widget
+                if sel.anchor.node != request.target || sel.focus.node != request.target {
+                    return false;
+                }
+                let mut buf = if let Some(b) = e.buffer() {
+                    b
+                } else {
+                    let b = text::TextBuffer::default();
+                    e.set_buffer(Some(b));
+                    e.buffer().unwrap()
+                };
+                let len = buf.length();
+                let a = (sel.anchor.character_index as i32).clamp(0, len);
+                let f = (sel.focus.character_index as i32).clamp(0, len);
+                if a == f {
+                    // Caret move
+                    buf.unselect();
+                    e.set_insert_position(a);
+                } else {
+                    let (start, end) = if a <= f { (a, f) } else { (f, a) };
+                    buf.select(start, end);
+                    e.set_insert_position(end);
+                }
+                true
+            }
+            _ => false,
+        }
+    }
 }
 
 #[allow(deprecated)]
@@ -515,9 +889,14 @@ impl Accessible for valuator::Slider {
         builder.set_max_numeric_value(self.maximum());
         builder.set_numeric_value_step(self.step());
         builder.add_action(Action::SetValue);
+        builder.add_action(Action::Increment);
+        builder.add_action(Action::Decrement);
         let id = node_widget_common(&mut builder, self, children);
         (id, builder)
     }
+    fn perform_action(&self, request: &ActionRequest) -> bool {
+        valuator_action(self, request)
+    }
 }
 
 impl Accessible for valuator::NiceSlider {
@@ -528,9 +907,14 @@ impl Accessible for valuator::NiceSlider {
         builder.set_max_numeric_value(self.maximum());
         builder.set_numeric_value_step(self.step());
         builder.add_action(Action::SetValue);
+        builder.add_action(Action::Increment);
+        builder.add_action(Action::Decrement);
         let id = node_widget_common(&mut builder, self, children);
         (id, builder)
     }
+    fn perform_action(&self, request: &ActionRequest) -> bool {
+        valuator_action(self, request)
+    }
 }
 
 impl Accessible for valuator::ValueSlider {
@@ -541,9 +925,14 @@ impl Accessible for valuator::ValueSlider {
         builder.set_max_numeric_value(self.maximum());
         builder.set_numeric_value_step(self.step());
         builder.add_action(Action::SetValue);
+        builder.add_action(Action::Increment);
+        builder.add_action(Action::Decrement);
         let id = node_widget_common(&mut builder, self, children);
         (id, builder)
     }
+    fn perform_action(&self, request: &ActionRequest) -> bool {
+        valuator_action(self, request)
+    }
 }
 
 impl Accessible for valuator::FillSlider {
@@ -554,9 +943,14 @@ impl Accessible for valuator::FillSlider {
         builder.set_max_numeric_value(self.maximum());
         builder.set_numeric_value_step(self.step());
         builder.add_action(Action::SetValue);
+        builder.add_action(Action::Increment);
+        builder.add_action(Action::Decrement);
         let id = node_widget_common(&mut builder, self, children);
         (id, builder)
     }
+    fn perform_action(&self, request: &ActionRequest) -> bool {
+        valuator_action(self, request)
+    }
 }
 
 impl Accessible for valuator::HorSlider {
@@ -567,9 +961,14 @@ impl Accessible for valuator::HorSlider {
         builder.set_max_numeric_value(self.maximum());
         builder.set_numeric_value_step(self.step());
         builder.add_action(Action::SetValue);
+        builder.add_action(Action::Increment);
+        builder.add_action(Action::Decrement);
         let id = node_widget_common(&mut builder, self, children);
         (id, builder)
     }
+    fn perform_action(&self, request: &ActionRequest) -> bool {
+        valuator_action(self, request)
+    }
 }
 
 impl Accessible for valuator::HorFillSlider {
@@ -580,9 +979,14 @@ impl Accessible for valuator::HorFillSlider {
         builder.set_max_numeric_value(self.maximum());
         builder.set_numeric_value_step(self.step());
         builder.add_action(Action::SetValue);
+        builder.add_action(Action::Increment);
+        builder.add_action(Action::Decrement);
         let id = node_widget_common(&mut builder, self, children);
         (id, builder)
     }
+    fn perform_action(&self, request: &ActionRequest) -> bool {
+        valuator_action(self, request)
+    }
 }
 
 impl Accessible for valuator::HorNiceSlider {
@@ -593,9 +997,14 @@ impl Accessible for valuator::HorNiceSlider {
         builder.set_max_numeric_value(self.maximum());
         builder.set_numeric_value_step(self.step());
         builder.add_action(Action::SetValue);
+        builder.add_action(Action::Increment);
+        builder.add_action(Action::Decrement);
         let id = node_widget_common(&mut builder, self, children);
         (id, builder)
     }
+    fn perform_action(&self, request: &ActionRequest) -> bool {
+        valuator_action(self, request)
+    }
 }
 
 impl Accessible for valuator::HorValueSlider {
@@ -606,9 +1015,14 @@ impl Accessible for valuator::HorValueSlider {
         builder.set_max_numeric_value(self.maximum());
         builder.set_numeric_value_step(self.step());
         builder.add_action(Action::SetValue);
+        builder.add_action(Action::Increment);
+        builder.add_action(Action::Decrement);
         let id = node_widget_common(&mut builder, self, children);
         (id, builder)
     }
+    fn perform_action(&self, request: &ActionRequest) -> bool {
+        valuator_action(self, request)
+    }
 }
 
 impl Accessible for valuator::Dial {
@@ -619,9 +1033,14 @@ impl Accessible for valuator::Dial {
         builder.set_max_numeric_value(self.maximum());
         builder.set_numeric_value_step(self.step());
         builder.add_action(Action::SetValue);
+        builder.add_action(Action::Increment);
+        builder.add_action(Action::Decrement);
         let id = node_widget_common(&mut builder, self, children);
         (id, builder)
     }
+    fn perform_action(&self, request: &ActionRequest) -> bool {
+        valuator_action(self, request)
+    }
 }
 
 impl Accessible for valuator::FillDial {
@@ -632,9 +1051,14 @@ impl Accessible for valuator::FillDial {
         builder.set_max_numeric_value(self.maximum());
         builder.set_numeric_value_step(self.step());
         builder.add_action(Action::SetValue);
+        builder.add_action(Action::Increment);
+        builder.add_action(Action::Decrement);
         let id = node_widget_common(&mut builder, self, children);
         (id, builder)
     }
+    fn perform_action(&self, request: &ActionRequest) -> bool {
+        valuator_action(self, request)
+    }
 }
 
 impl Accessible for valuator::LineDial {
@@ -645,9 +1069,14 @@ impl Accessible for valuator::LineDial {
         builder.set_max_numeric_value(self.maximum());
         builder.set_numeric_value_step(self.step());
         builder.add_action(Action::SetValue);
+        builder.add_action(Action::Increment);
+        builder.add_action(Action::Decrement);
         let id = node_widget_common(&mut builder, self, children);
         (id, builder)
     }
+    fn perform_action(&self, request: &ActionRequest) -> bool {
+        valuator_action(self, request)
+    }
 }
 
 impl Accessible for valuator::Counter {
@@ -658,9 +1087,14 @@ impl Accessible for valuator::Counter {
         builder.set_max_numeric_value(self.maximum());
         builder.set_numeric_value_step(self.step());
         builder.add_action(Action::SetValue);
+        builder.add_action(Action::Increment);
+        builder.add_action(Action::Decrement);
         let id = node_widget_common(&mut builder, self, children);
         (id, builder)
     }
+    fn perform_action(&self, request: &ActionRequest) -> bool {
+        valuator_action(self, request)
+    }
 }
 
 impl Accessible for valuator::Roller {
@@ -671,9 +1105,14 @@ impl Accessible for valuator::Roller {
         builder.set_max_numeric_value(self.maximum());
         builder.set_numeric_value_step(self.step());
         builder.add_action(Action::SetValue);
+        builder.add_action(Action::Increment);
+        builder.add_action(Action::Decrement);
         let id = node_widget_common(&mut builder, self, children);
         (id, builder)
     }
+    fn perform_action(&self, request: &ActionRequest) -> bool {
+        valuator_action(self, request)
+    }
 }
 
 impl Accessible for valuator::ValueInput {
@@ -684,9 +1123,14 @@ impl Accessible for valuator::ValueInput {
         builder.set_max_numeric_value(self.maximum());
         builder.set_numeric_value_step(self.step());
         builder.add_action(Action::SetValue);
+        builder.add_action(Action::Increment);
+        builder.add_action(Action::Decrement);
         let id = node_widget_common(&mut builder, self, children);
         (id, builder)
     }
+    fn perform_action(&self, request: &ActionRequest) -> bool {
+        valuator_action(self, request)
+    }
 }
 
 impl Accessible for valuator::ValueOutput {
@@ -697,9 +1141,14 @@ impl Accessible for valuator::ValueOutput {
         builder.set_max_numeric_value(self.maximum());
         builder.set_numeric_value_step(self.step());
         builder.add_action(Action::SetValue);
+        builder.add_action(Action::Increment);
+        builder.add_action(Action::Decrement);
         let id = node_widget_common(&mut builder, self, children);
         (id, builder)
     }
+    fn perform_action(&self, request: &ActionRequest) -> bool {
+        valuator_action(self, request)
+    }
 }
 
 impl Accessible for valuator::Scrollbar {
@@ -712,6 +1161,9 @@ impl Accessible for valuator::Scrollbar {
         let id = node_widget_common(&mut builder, self, children);
         (id, builder)
     }
+    fn perform_action(&self, request: &ActionRequest) -> bool {
+        valuator_action(self, request)
+    }
 }
 
 impl Accessible for menu::MenuBar {
@@ -722,9 +1174,112 @@ impl Accessible for menu::MenuBar {
     }
 }
 
+impl Accessible for menu::MenuButton {
+    fn make_node(&self, children: &[NodeId]) -> (NodeId, Node) {
+        let mut builder = Node::new(Role::Button);
+        builder.add_action(Action::Focus);
+        builder.add_action(Action::Click);
+        builder.add_action(Action::Expand);
+        builder.set_has_popup(accesskit::HasPopup::Menu);
+        let id = node_widget_common(&mut builder, self, children);
+        (id, builder)
+    }
+    fn perform_action(&self, request: &ActionRequest) -> bool {
+        match request.action {
+            // The popup runs its own event loop until it's closed, so it's opened from a
+            // timeout rather than while action requests are being drained
+            Action::Expand => {
+                crate::popup::expect(self);
+                let btn = self.clone();
+                app::add_timeout3(0.0, move |_| {
+                    let _ = btn.popup();
+                });
+                true
+            }
+            // Only offered while the popup can be closed, see `popup::can_close`
+            Action::Collapse => crate::popup::close(),
+            _ => false,
+        }
+    }
+}
+
 impl Accessible for menu::Choice {
     fn make_node(&self, children: &[NodeId]) -> (NodeId, Node) {
-        let mut builder = Node::new(Role::MenuListPopup);
+        let mut builder = Node::new(Role::ComboBox);
+        if let Some(val) = self.choice() {
+            builder.set_value(&*val);
+        }
+        builder.add_action(Action::Focus);
+        builder.add_action(Action::SetValue);
+        builder.set_has_popup(accesskit::HasPopup::Listbox);
+        let open = crate::popup::shown(&self.as_base_widget()).is_some();
+        if !open {
+            builder.add_action(Action::Expand);
+        } else if crate::popup::can_close() {
+            builder.add_action(Action::Collapse);
+        }
+        builder.set_expanded(open);
+        let id = node_widget_common(&mut builder, self, children);
+        (id, builder)
+    }
+    fn perform_action(&self, request: &ActionRequest) -> bool {
+        let mut c = self.clone();
+        match (request.action, &request.data) {
+            // Like a click on the choice: the popup runs its own event loop, so it's
+            // opened from a timeout rather than while action requests are being drained
+            (Action::Expand, _) => {
+                crate::popup::expect(self);
+                app::add_timeout3(0.0, move |_| {
+                    let Some(items) = c.menu() else {
+                        return;
+                    };
+                    let picked = items.pulldown(c.x(), c.y(), c.w(), c.h(), c.mvalue(), Some(&c));
+                    if let Some(item) = picked.filter(|it| !it.is_submenu()) {
+                        let addr = unsafe { item.as_ptr() } as usize;
+                        crate::menus::pick(&c.as_base_widget(), addr);
+                    }
+                });
+                true
+            }
+            // Only offered while the popup can be closed, see `popup::can_close`
+            (Action::Collapse, _) => crate::popup::close(),
+            // By label
+            (Action::SetValue, Some(ActionData::Value(s))) => {
+                let idx = c.find_index(s);
+                if idx >= 0 {
+                    c.set_value(idx);
+                }
+                true
+            }
+            // By index
+            (Action::SetValue, Some(ActionData::NumericValue(n))) => {
+                let total = c.size();
+                if total > 0 {
+                    c.set_value((n.round() as i32).clamp(0, total - 1));
+                }
+                true
+            }
+            _ => false,
+        }
+    }
+}
+
+impl Accessible for browser::Browser {
+    fn make_node(&self, children: &[NodeId]) -> (NodeId, Node) {
+        let mut builder = Node::new(Role::ListBox);
+        builder.add_action(Action::Focus);
+        if self.get_type::<browser::BrowserType>() == browser::BrowserType::Multi {
+            builder.set_multiselectable();
+        }
+        let id = node_widget_common(&mut builder, self, children);
+        (id, builder)
+    }
+}
+
+impl Accessible for browser::CheckBrowser {
+    fn make_node(&self, children: &[NodeId]) -> (NodeId, Node) {
+        let mut builder = Node::new(Role::ListBox);
+        builder.add_action(Action::Focus);
         let id = node_widget_common(&mut builder, self, children);
         (id, builder)
     }
@@ -749,11 +1304,39 @@ impl Accessible for tree::Tree {
 impl Accessible for group::Scroll {
     fn make_node(&self, children: &[NodeId]) -> (NodeId, Node) {
         let mut builder = Node::new(Role::ScrollView);
+        builder.set_scroll_x(self.xposition() as f64);
+        builder.set_scroll_x_min(0.0);
+        builder.set_scroll_x_max(self.hscrollbar().maximum().max(0.0));
+        builder.set_scroll_y(self.yposition() as f64);
+        builder.set_scroll_y_min(0.0);
+        builder.set_scroll_y_max(self.scrollbar().maximum().max(0.0));
         builder.add_action(Action::ScrollDown);
         builder.add_action(Action::ScrollUp);
         let id = node_widget_common(&mut builder, self, children);
         (id, builder)
     }
+    fn perform_action(&self, request: &ActionRequest) -> bool {
+        let mut s = self.clone();
+        let (mut dx, mut dy) = (s.w(), s.h());
+        if let Some(ActionData::ScrollUnit(ScrollUnit::Item)) = request.data {
+            dx /= 10;
+            dy /= 10;
+        }
+        let (dx, dy) = match request.action {
+            Action::ScrollDown => (0, dy),
+            Action::ScrollUp => (0, -dy),
+            Action::ScrollRight => (dx, 0),
+            Action::ScrollLeft => (-dx, 0),
+            _ => return false,
+        };
+        let max_x = s.hscrollbar().maximum().max(0.0) as i32;
+        let max_y = s.scrollbar().maximum().max(0.0) as i32;
+        let x = (s.xposition() + dx).clamp(0, max_x);
+        let y = (s.yposition() + dy).clamp(0, max_y);
+        s.scroll_to(x, y);
+        s.redraw();
+        true
+    }
 }
 
 impl Accessible for group::Flex {
@@ -781,4 +1364,183 @@ impl Accessible for misc::Progress {
         let id = node_widget_common(&mut builder, self, children);
         (id, builder)
     }
+    fn perform_action(&self, request: &ActionRequest) -> bool {
+        let n = match (request.action, &request.data) {
+            (Action::SetValue, Some(ActionData::NumericValue(n))) => *n,
+            (Action::SetValue, Some(ActionData::Value(s))) => match s.parse::<f64>() {
+                Ok(n) => n,
+                Err(_) => return false,
+            },
+            _ => return false,
+        };
+        self.clone().set_value(n);
+        true
+    }
+}
+
+impl Accessible for misc::Spinner {
+    fn make_node(&self, children: &[NodeId]) -> (NodeId, Node) {
+        let mut builder = Node::new(Role::SpinButton);
+        builder.set_numeric_value(self.value());
+        builder.set_min_numeric_value(self.minimum());
+        builder.set_max_numeric_value(self.maximum());
+        builder.set_numeric_value_step(self.step());
+        builder.add_action(Action::Focus);
+        builder.add_action(Action::SetValue);
+        builder.add_action(Action::Increment);
+        builder.add_action(Action::Decrement);
+        let id = node_widget_common(&mut builder, self, children);
+        (id, builder)
+    }
+    fn perform_action(&self, request: &ActionRequest) -> bool {
+        let mut s = self.clone();
+        let value = match (request.action, &request.data) {
+            (Action::SetValue, Some(ActionData::NumericValue(n))) => *n,
+            (Action::SetValue, Some(ActionData::Value(v))) => match v.parse::<f64>() {
+                Ok(n) => n,
+                Err(_) => return false,
+            },
+            (Action::Increment, _) => s.value() + s.step(),
+            (Action::Decrement, _) => s.value() - s.step(),
+            _ => return false,
+        };
+        let (lo, hi) = (s.minimum().min(s.maximum()), s.minimum().max(s.maximum()));
+        s.set_value(value.clamp(lo, hi));
+        s.do_callback();
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Every entry of `BUILTINS` is the first to match a widget of its own type, so none
+    /// is shadowed by the impl of a base class listed before it.
+    #[test]
+    #[allow(deprecated)]
+    fn builtins_are_reachable() {
+        // Inside a window, so that the window below is a subwindow that needs no display
+        let mut outer = window::Window::default();
+        let widgets: Vec<(widget::Widget, Role)> = vec![
+            (
+                button::RadioRoundButton::default().as_base_widget(),
+                Role::RadioButton,
+            ),
+            (
+                button::RadioButton::default().as_base_widget(),
+                Role::RadioButton,
+            ),
+            (
+                button::CheckButton::default().as_base_widget(),
+                Role::CheckBox,
+            ),
+            (
+                button::ToggleButton::default().as_base_widget(),
+                Role::Button,
+            ),
+            (button::Button::default().as_base_widget(), Role::Button),
+            (
+                output::MultilineOutput::default().as_base_widget(),
+                Role::Paragraph,
+            ),
+            (output::Output::default().as_base_widget(), Role::Label),
+            (
+                input::MultilineInput::default().as_base_widget(),
+                Role::MultilineTextInput,
+            ),
+            (input::IntInput::default().as_base_widget(), Role::TextInput),
+            (
+                input::FloatInput::default().as_base_widget(),
+                Role::TextInput,
+            ),
+            (input::Input::default().as_base_widget(), Role::TextInput),
+            (
+                valuator::Scrollbar::default().as_base_widget(),
+                Role::ScrollBar,
+            ),
+            (
+                valuator::HorValueSlider::default().as_base_widget(),
+                Role::Slider,
+            ),
+            (
+                valuator::ValueSlider::default().as_base_widget(),
+                Role::Slider,
+            ),
+            (
+                valuator::HorFillSlider::default().as_base_widget(),
+                Role::Slider,
+            ),
+            (
+                valuator::FillSlider::default().as_base_widget(),
+                Role::Slider,
+            ),
+            (
+                valuator::HorNiceSlider::default().as_base_widget(),
+                Role::Slider,
+            ),
+            (
+                valuator::NiceSlider::default().as_base_widget(),
+                Role::Slider,
+            ),
+            (
+                valuator::HorSlider::default().as_base_widget(),
+                Role::Slider,
+            ),
+            (valuator::Slider::default().as_base_widget(), Role::Slider),
+            (valuator::FillDial::default().as_base_widget(), Role::Slider),
+            (valuator::LineDial::default().as_base_widget(), Role::Slider),
+            (valuator::Dial::default().as_base_widget(), Role::Slider),
+            (valuator::Counter::default().as_base_widget(), Role::Slider),
+            (valuator::Roller::default().as_base_widget(), Role::Slider),
+            (
+                valuator::ValueInput::default().as_base_widget(),
+                Role::Slider,
+            ),
+            (
+                valuator::ValueOutput::default().as_base_widget(),
+                Role::Slider,
+            ),
+            (
+                misc::Progress::default().as_base_widget(),
+                Role::ProgressIndicator,
+            ),
+            (misc::Spinner::default().as_base_widget(), Role::SpinButton),
+            (menu::MenuBar::default().as_base_widget(), Role::MenuBar),
+            (menu::MenuButton::default().as_base_widget(), Role::Button),
+            (menu::Choice::default().as_base_widget(), Role::ComboBox),
+            (frame::Frame::default().as_base_widget(), Role::Label),
+            (
+                browser::CheckBrowser::default().as_base_widget(),
+                Role::ListBox,
+            ),
+            (browser::Browser::default().as_base_widget(), Role::ListBox),
+            (
+                text::SimpleTerminal::default().as_base_widget(),
+                Role::Terminal,
+            ),
+            (
+                text::TextEditor::default().as_base_widget(),
+                Role::MultilineTextInput,
+            ),
+            (
+                text::TextDisplay::default().as_base_widget(),
+                Role::Paragraph,
+            ),
+            (table::Table::default().as_base_widget(), Role::Table),
+            (tree::Tree::default().as_base_widget(), Role::Tree),
+            (group::Scroll::default().as_base_widget(), Role::ScrollView),
+            (group::Flex::default().as_base_widget(), Role::Group),
+            (window::Window::default().as_base_widget(), Role::Window),
+            (group::Group::default().as_base_widget(), Role::Group),
+        ];
+        outer.end();
+        assert_eq!(widgets.len(), BUILTINS.len());
+        for (i, (w, role)) in widgets.iter().enumerate() {
+            let first = BUILTINS.iter().position(|f| f(w).is_some());
+            assert_eq!(first, Some(i), "entry {i} is shadowed");
+            let (_, node) = accessible_for_widget(w).unwrap().make_node(&[]);
+            assert_eq!(node.role(), *role, "entry {i}");
+        }
+    }
 }
@@ -0,0 +1,279 @@
+use accesskit::{Action, ActionRequest, Node, NodeId, Role, Toggled};
+use fltk::{
+    browser::{self, BrowserType},
+    prelude::*,
+    widget,
+};
+
+use crate::ids::child_node_id;
+use crate::targets::Targets;
+
+/// The text of a browser line as drawn, without the format codes at the start of each
+/// field (see `Fl_Browser::item_draw`). Two format characters in a row stand for one.
+fn strip_format(field: &str, format_char: char) -> &str {
+    let mut s = field;
+    if format_char == '\0' {
+        return s;
+    }
+    while let Some(rest) = s.strip_prefix(format_char) {
+        let mut chars = rest.chars();
+        match chars.next() {
+            None => return rest,
+            Some(c) if c == format_char => return rest,
+            Some('.') => return chars.as_str(),
+            // Colors, fonts and sizes are followed by their number
+            Some('B' | 'C' | 'F' | 'S') => {
+                s = chars
+                    .as_str()
+                    .trim_start_matches(|c: char| c.is_ascii_digit());
+            }
+            Some(_) => s = chars.as_str(),
+        }
+    }
+    s
+}
+
+/// The fields of a line. Like fltk, lines are only split when the browser has column
+/// widths, into at most one more field than there are widths.
+fn fields<B: BrowserExt>(b: &B, text: &str) -> Vec<String> {
+    let widths = b.column_widths().iter().take_while(|w| **w != 0).count();
+    let parts: Vec<&str> = if widths == 0 {
+        vec![text]
+    } else {
+        text.splitn(widths + 1, b.column_char()).collect()
+    };
+    parts
+        .into_iter()
+        .map(|f| strip_format(f, b.format_char()).to_string())
+        .collect()
+}
+
+/// The options of the lines of `b`, whose node is `owner`.
+fn browser_lines<B: BrowserExt + WidgetBase>(
+    b: &B,
+    owner: NodeId,
+    out: &mut Vec<(NodeId, Node)>,
+    targets: &mut Targets,
+) -> Vec<NodeId> {
+    let selectable = b.get_type::<BrowserType>() != BrowserType::Normal;
+    let total = b.size().max(0) as usize;
+    let mut ids = Vec::with_capacity(total);
+    for line in 1..=b.size() {
+        let id = child_node_id(owner, line as u64);
+        let mut node = Node::new(Role::ListBoxOption);
+        let fields = fields(b, &b.text(line).unwrap_or_default());
+        node.set_label(fields.join(" "));
+        if fields.len() > 1 {
+            for (col, field) in fields.iter().enumerate() {
+                let mut cell = Node::new(Role::Cell);
+                cell.set_label(field.as_str());
+                let cell_id = child_node_id(id, col as u64);
+                node.push_child(cell_id);
+                out.push((cell_id, cell));
+            }
+        }
+        if selectable {
+            node.set_selected(b.selected(line));
+            node.add_action(Action::Click);
+        }
+        node.add_action(Action::ScrollIntoView);
+        node.set_position_in_set(line as usize);
+        node.set_size_of_set(total);
+        targets.insert_browser_line(id, b, line);
+        ids.push(id);
+        out.push((id, node));
+    }
+    ids
+}
+
+/// The options of the lines of `c`, whose node is `owner`, as checkboxes.
+fn check_browser_lines(
+    c: &browser::CheckBrowser,
+    owner: NodeId,
+    out: &mut Vec<(NodeId, Node)>,
+    targets: &mut Targets,
+) -> Vec<NodeId> {
+    let total = c.nitems();
+    let mut ids = Vec::with_capacity(total);
+    for line in 1..=total as i32 {
+        let mut node = Node::new(Role::ListBoxOption);
+        node.set_label(c.text(line).unwrap_or_default());
+        node.set_toggled(if c.checked(line) {
+            Toggled::True
+        } else {
+            Toggled::False
+        });
+        node.set_selected(c.value() == line);
+        node.add_action(Action::Click);
+        node.add_action(Action::ScrollIntoView);
+        node.set_position_in_set(line as usize);
+        node.set_size_of_set(total);
+        let id = child_node_id(owner, line as u64);
+        targets.insert_browser_line(id, c, line);
+        ids.push(id);
+        out.push((id, node));
+    }
+    ids
+}
+
+/// The nodes of a browser's lines, if `w` is a browser.
+pub(crate) fn line_nodes(
+    w: &widget::Widget,
+    owner: NodeId,
+    out: &mut Vec<(NodeId, Node)>,
+    targets: &mut Targets,
+) -> Option<Vec<NodeId>> {
+    let p = w.as_widget_ptr();
+    // HoldBrowser, SelectBrowser, MultiBrowser and FileBrowser derive from Browser
+    if let Some(b) = browser::Browser::from_dyn_widget_ptr(p) {
+        return Some(browser_lines(&b, owner, out, targets));
+    }
+    let c = browser::CheckBrowser::from_dyn_widget_ptr(p)?;
+    Some(check_browser_lines(&c, owner, out, targets))
+}
+
+/// How many lines the browser `w` has.
+pub(crate) fn line_count(w: &widget::Widget) -> i32 {
+    let p = w.as_widget_ptr();
+    if let Some(b) = browser::Browser::from_dyn_widget_ptr(p) {
+        return b.size();
+    }
+    browser::CheckBrowser::from_dyn_widget_ptr(p).map_or(0, |c| c.nitems() as i32)
+}
+
+/// Acts on `line` of the browser `w` like a click on it: toggles its selection, leaving no
+/// other line selected unless it's a multi browser, or toggles its check in a check
+/// browser, then runs the callback. Clicking the selected line of a hold or select browser
+/// is the one way to deselect it, like Ctrl+click in a multi browser. `ScrollIntoView`
+/// scrolls to the line.
+pub(crate) fn perform_line_action(w: &widget::Widget, line: i32, request: &ActionRequest) {
+    let p = w.as_widget_ptr();
+    if let Some(mut b) = browser::Browser::from_dyn_widget_ptr(p) {
+        match request.action {
+            Action::Click if b.get_type::<BrowserType>() != BrowserType::Normal => {
+                let on = !b.selected(line);
+                if on {
+                    b.select(line);
+                } else {
+                    b.deselect(line);
+                }
+                b.set_changed();
+                b.do_callback();
+            }
+            Action::ScrollIntoView => b.make_visible(line),
+            _ => (),
+        }
+    } else if let Some(mut c) = browser::CheckBrowser::from_dyn_widget_ptr(p) {
+        if request.action == Action::ScrollIntoView {
+            scroll_to(&mut c, line);
+            return;
+        }
+        if request.action != Action::Click {
+            return;
+        }
+        if c.checked(line) {
+            // There's no way to uncheck a single line
+            let others: Vec<i32> = (1..=c.nitems() as i32)
+                .filter(|l| *l != line && c.checked(*l))
+                .collect();
+            c.check_none();
+            for l in others {
+                c.set_checked(l);
+            }
+        } else {
+            c.set_checked(line);
+        }
+        c.set_changed();
+        c.do_callback();
+    }
+}
+
+/// Scrolls `c` just enough for `line` to be in view. Check browsers don't have
+/// `make_visible`, but their lines are all as high as their text, see
+/// `Fl_Check_Browser::item_height`.
+fn scroll_to(c: &mut browser::CheckBrowser, line: i32) {
+    let line_h = c.text_size() + 2;
+    let top = (line - 1) * line_h;
+    let mut view = c.h() - c.frame().dh();
+    let hbar = c.hscrollbar();
+    if hbar.visible() {
+        view -= hbar.h();
+    }
+    if top < c.position() {
+        c.set_position(top);
+    } else if top + line_h > c.position() + view {
+        c.set_position(top + line_h - view);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn request(action: Action) -> ActionRequest {
+        ActionRequest {
+            action,
+            target: NodeId(0),
+            data: None,
+        }
+    }
+
+    #[test]
+    fn format_codes() {
+        assert_eq!(strip_format("plain", '@'), "plain");
+        assert_eq!(strip_format("@@home", '@'), "@home");
+        assert_eq!(strip_format("@.@b not bold", '@'), "@b not bold");
+        assert_eq!(strip_format("@B12@cCentered", '@'), "Centered");
+        assert_eq!(strip_format("@S18@C4@bBig", '@'), "Big");
+        assert_eq!(strip_format("@B12", '@'), "");
+        assert_eq!(strip_format("#bBold", '#'), "Bold");
+        assert_eq!(strip_format("@bBold", '\0'), "@bBold");
+    }
+
+    #[test]
+    fn columns() {
+        let mut b = browser::Browser::default();
+        assert_eq!(fields(&b, "a\tb"), ["a\tb"]);
+        // One more field than there are widths, the last one taking the rest
+        b.set_column_widths(&[20, 20]);
+        assert_eq!(fields(&b, "@ba\tb\tc\td"), ["a", "b", "c\td"]);
+        b.set_column_char(';');
+        assert_eq!(fields(&b, "a;b\tc"), ["a", "b\tc"]);
+    }
+
+    #[test]
+    fn single_selection_can_be_undone() {
+        for kind in [BrowserType::Hold, BrowserType::Select] {
+            let mut b = browser::Browser::default();
+            b.set_type(kind);
+            b.add("One");
+            b.add("Two");
+            let w = b.as_base_widget();
+            perform_line_action(&w, 1, &request(Action::Click));
+            perform_line_action(&w, 2, &request(Action::Click));
+            assert!(!b.selected(1) && b.selected(2));
+            perform_line_action(&w, 2, &request(Action::Click));
+            assert!(!b.selected(1) && !b.selected(2));
+        }
+    }
+
+    #[test]
+    fn check_lines_scroll_into_view() {
+        let mut c = browser::CheckBrowser::new(0, 0, 100, 50, None);
+        for i in 0..20 {
+            c.add(&i.to_string(), false);
+        }
+        let mut out = Vec::new();
+        check_browser_lines(&c, NodeId(1), &mut out, &mut Targets::default());
+        assert!(out
+            .iter()
+            .all(|(_, n)| n.supports_action(Action::ScrollIntoView)));
+        let w = c.as_base_widget();
+        perform_line_action(&w, 20, &request(Action::ScrollIntoView));
+        let line_h = c.text_size() + 2;
+        assert!(c.position() > 0);
+        assert!(c.position() + c.h() >= 20 * line_h);
+        perform_line_action(&w, 1, &request(Action::ScrollIntoView));
+        assert_eq!(c.position(), 0);
+    }
+}
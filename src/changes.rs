@@ -0,0 +1,216 @@
+use fltk::{app, enums::Event, prelude::*};
+use std::cell::{Cell, RefCell};
+use std::rc::{Rc, Weak};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+
+thread_local! {
+    /// Everything that wants to hear about events, see [`observe`]
+    static OBSERVERS: RefCell<Vec<Weak<Changes>>> = const { RefCell::new(Vec::new()) };
+}
+
+/// Whether handling `ev` may have changed what the tree shows: values, toggles,
+/// selections, scroll positions, the focus or the scale of the windows. Pasted and dropped
+/// text arrives in an event of its own, after the click or key that asked for it.
+pub(crate) fn affects_tree(ev: Event) -> bool {
+    matches!(
+        ev,
+        Event::Push
+            | Event::Released
+            | Event::Drag
+            | Event::Focus
+            | Event::Unfocus
+            | Event::MouseWheel
+            | Event::Shortcut
+            | Event::KeyDown
+            | Event::KeyUp
+            | Event::Paste
+            | Event::DndRelease
+            | Event::ScreenConfigChanged
+    )
+}
+
+/// What the next update has to cover.
+pub(crate) enum Refresh {
+    None,
+    Full,
+    /// Only the nodes of the widgets at these addresses
+    Widgets(Vec<usize>),
+}
+
+/// Requests made through an [`Invalidator`], possibly from other threads.
+#[derive(Default)]
+struct Pending {
+    /// Set once the UI thread has been woken up for the current requests
+    woken: AtomicBool,
+    full: AtomicBool,
+    widgets: Mutex<Vec<usize>>,
+}
+
+/// Collects the reasons for an update during an event loop iteration, so that any
+/// number of them results in a single update at the end of it.
+#[derive(Default)]
+pub(crate) struct Changes {
+    dirty: Cell<bool>,
+    /// Address of the focused widget as of the last update, 0 for none
+    focus: Cell<usize>,
+    pending: Arc<Pending>,
+}
+
+impl Changes {
+    pub(crate) fn invalidate(&self) {
+        self.dirty.set(true);
+    }
+
+    pub(crate) fn invalidator(&self) -> Invalidator {
+        Invalidator {
+            pending: self.pending.clone(),
+        }
+    }
+
+    /// What is due, either because something was invalidated or because the focus
+    /// moved without an event we saw. Resets all requests.
+    pub(crate) fn take(&self) -> Refresh {
+        let focus = app::focus().map_or(0, |w| w.as_widget_ptr() as usize);
+        let moved = self.focus.replace(focus) != focus;
+        self.pending.woken.store(false, Ordering::SeqCst);
+        let full = self.pending.full.swap(false, Ordering::SeqCst);
+        let widgets = std::mem::take(&mut *self.pending.widgets.lock().unwrap());
+        if self.dirty.replace(false) || moved || full {
+            Refresh::Full
+        } else if !widgets.is_empty() {
+            Refresh::Widgets(widgets)
+        } else {
+            Refresh::None
+        }
+    }
+}
+
+/// Requests updates of an [`AccessibilityContext`](crate::AccessibilityContext) from
+/// any thread, like a worker that changed widgets or the callback of `app::awake_callback`.
+///
+/// Requests are picked up by the UI thread, and any number of them made before it gets
+/// to them result in a single update.
+#[derive(Clone)]
+pub struct Invalidator {
+    pending: Arc<Pending>,
+}
+
+impl Invalidator {
+    /// Request an update of the whole tree, for when widgets were added, removed or
+    /// moved around.
+    pub fn invalidate(&self) {
+        self.pending.full.store(true, Ordering::SeqCst);
+        self.wake();
+    }
+
+    /// Request an update of `w`'s nodes only, for when its value, label or state changed.
+    pub fn invalidate_widget<W: WidgetExt>(&self, w: &W) {
+        let addr = w.as_widget_ptr() as usize;
+        let mut widgets = self.pending.widgets.lock().unwrap();
+        if !widgets.contains(&addr) {
+            widgets.push(addr);
+        }
+        drop(widgets);
+        self.wake();
+    }
+
+    fn wake(&self) {
+        if !self.pending.woken.swap(true, Ordering::SeqCst) {
+            app::awake();
+        }
+    }
+}
+
+/// Invalidate `changes` whenever an event that may affect the tree is dispatched, to any
+/// window, for as long as it lives.
+///
+/// This wraps fltk's event dispatch instead of setting a `handle` callback on a window,
+/// which would replace the one the application set. Every event still goes to fltk's
+/// default dispatch, so the application sees exactly what it would without us. There's
+/// only room for one dispatch function though, and no way to get the one installed
+/// before, see [`dispatch`].
+pub(crate) fn observe(changes: &Rc<Changes>) {
+    OBSERVERS.with(|o| {
+        let mut o = o.borrow_mut();
+        if o.is_empty() {
+            unsafe { app::event_dispatch(dispatch) };
+        }
+        o.retain(|c| c.strong_count() > 0);
+        o.push(Rc::downgrade(changes));
+    });
+}
+
+/// The event dispatch function installed by
+/// [`run_with_accessibility`](crate::AccessibleApp::run_with_accessibility), which notes
+/// the events that may change the tree and hands every event to fltk.
+///
+/// fltk keeps a single dispatch function, so one the application installs with
+/// `app::event_dispatch` replaces this one, and this one replaces any installed before.
+/// Applications that need their own should install it after attaching, and call this
+/// instead of `app::handle_raw` for the events they let through:
+/// ```rust,no_run
+/// use fltk::{app, enums::Event};
+/// fn my_dispatch(ev: Event, win: app::WindowPtr) -> bool {
+///     if ev == Event::Close {
+///         return false;
+///     }
+///     fltk_accesskit::dispatch(ev, win)
+/// }
+/// unsafe { app::event_dispatch(my_dispatch) };
+/// ```
+pub fn dispatch(ev: Event, win: app::WindowPtr) -> bool {
+    // While a popup is open, the pointer moving changes its highlighted item and whether
+    // it can be closed
+    if affects_tree(ev) || (ev == Event::Move && crate::popup::is_open()) {
+        OBSERVERS.with(|o| {
+            for c in o.borrow().iter().filter_map(Weak::upgrade) {
+                c.invalidate();
+            }
+        });
+    }
+    crate::popup::before(ev, win);
+    let handled = unsafe { app::handle_raw(ev, win) };
+    crate::popup::after(ev);
+    handled
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use fltk::button;
+
+    #[test]
+    fn requests_coalesce() {
+        let changes = Changes::default();
+        let a = button::Button::default();
+        let b = button::Button::default();
+        let invalidator = changes.invalidator();
+        assert!(matches!(changes.take(), Refresh::None));
+
+        invalidator.invalidate_widget(&a);
+        invalidator.invalidate_widget(&b);
+        invalidator.invalidate_widget(&a);
+        assert!(changes.pending.woken.load(Ordering::SeqCst));
+        match changes.take() {
+            Refresh::Widgets(addrs) => assert_eq!(
+                addrs,
+                [a.as_widget_ptr() as usize, b.as_widget_ptr() as usize]
+            ),
+            _ => panic!("expected a widgets refresh"),
+        }
+        assert!(!changes.pending.woken.load(Ordering::SeqCst));
+        assert!(matches!(changes.take(), Refresh::None));
+
+        // A full update covers the widgets too
+        invalidator.invalidate_widget(&a);
+        invalidator.invalidate();
+        assert!(matches!(changes.take(), Refresh::Full));
+        assert!(matches!(changes.take(), Refresh::None));
+
+        invalidator.invalidate_widget(&b);
+        changes.invalidate();
+        assert!(matches!(changes.take(), Refresh::Full));
+        assert!(matches!(changes.take(), Refresh::None));
+    }
+}
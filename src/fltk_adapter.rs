@@ -1,18 +1,22 @@
-#![allow(unused_imports)]
-#![allow(unused_variables)]
 use accesskit::{
-    Action, ActionData, ActionHandler, ActionRequest, ActivationHandler, DeactivationHandler, Node,
-    NodeId, Point, Rect, Size, Tree, TreeUpdate,
+    ActionHandler, ActionRequest, ActivationHandler, DeactivationHandler, Node, NodeId, Tree,
+    TreeUpdate,
 };
-use fltk::{button, enums::*, input, misc, prelude::*, text, utils, valuator, widget, *};
+#[cfg(not(any(target_os = "windows", target_os = "macos")))]
+use accesskit::{Point, Rect, Size};
+use fltk::{app, enums::Event, prelude::*, window};
 use std::cell::RefCell;
 use std::rc::Rc;
 
 use crate::platform_adapter;
 
+/// Hands out the tree built when the adapter was made. The platform adapter may ask for it
+/// from a thread of its own, where neither fltk nor our ids can be used, so everything is
+/// worked out up front on the UI thread.
 pub(crate) struct FltkActivationHandler {
     pub wids: Vec<(NodeId, Node)>,
     pub win_id: NodeId,
+    pub focus: NodeId,
 }
 
 impl ActivationHandler for FltkActivationHandler {
@@ -20,12 +24,7 @@ impl ActivationHandler for FltkActivationHandler {
         Some(TreeUpdate {
             nodes: self.wids.clone(),
             tree: Some(Tree::new(self.win_id)),
-            focus: if let Some(focused) = app::focus() {
-                let focused = focused.as_widget_ptr() as usize as u64;
-                NodeId(focused)
-            } else {
-                self.win_id
-            },
+            focus: self.focus,
         })
     }
 }
@@ -47,6 +46,36 @@ impl DeactivationHandler for FltkDeactivationHandler {
     fn deactivate_accessibility(&mut self) {}
 }
 
+/// The bounds of `win` with and without its decorations, in physical screen coordinates.
+#[cfg(not(any(target_os = "windows", target_os = "macos")))]
+fn root_window_bounds(win: &window::Window, scale: f64) -> (Rect, Rect) {
+    // The frame is the same width on the left, right and bottom, the rest is the title bar
+    let border = (win.decorated_w() - win.w()) / 2;
+    let title = win.decorated_h() - win.h() - border;
+    let outer = Rect::from_origin_size(
+        Point {
+            x: (win.x() - border) as f64 * scale,
+            y: (win.y() - title) as f64 * scale,
+        },
+        Size {
+            width: win.decorated_w() as f64 * scale,
+            height: win.decorated_h() as f64 * scale,
+        },
+    );
+    // Client-area origin (top-left) in root/screen coordinates
+    let inner = Rect::from_origin_size(
+        Point {
+            x: win.x() as f64 * scale,
+            y: win.y() as f64 * scale,
+        },
+        Size {
+            width: win.w() as f64 * scale,
+            height: win.h() as f64 * scale,
+        },
+    );
+    (outer, inner)
+}
+
 #[derive(Clone)]
 pub struct Adapter {
     adapter: Rc<RefCell<platform_adapter::Adapter>>,
@@ -54,388 +83,38 @@ pub struct Adapter {
 
 impl Adapter {
     pub fn new(window: &window::Window, source: impl 'static + ActivationHandler + Send) -> Self {
+        Self::with_router(window, source, |req| {
+            // Without a tree of our own, accept any live widget that was given this id
+            match crate::ids::widget_for(req.target) {
+                Some(w) => crate::accessible::perform_action(&w, None, &req),
+                None if cfg!(debug_assertions) => eprintln!(
+                    "fltk-accesskit: ignoring {:?} request for unknown node {:?}",
+                    req.action, req.target
+                ),
+                None => (),
+            }
+        })
+    }
+
+    /// Like [`Self::new`], handing every action request to `route` on the UI thread.
+    pub(crate) fn with_router(
+        window: &window::Window,
+        source: impl 'static + ActivationHandler + Send,
+        mut route: impl FnMut(ActionRequest) + 'static,
+    ) -> Self {
         let (tx, rx) = app::channel::<ActionRequest>();
         let action_handler = FltkActionHandler { tx };
         let this = Self::with_action_handler(window, source, action_handler);
-        // Drain action requests on the UI thread when awakened.
-        let rx = Rc::new(RefCell::new(rx));
-        app::awake_callback({
-            let rx = rx.clone();
-            move || {
-                while let Some(req) = rx.borrow_mut().recv() {
-                    unsafe {
-                        let mut w = widget::Widget::from_widget_ptr(req.target.0 as _);
-                        match req.action {
-                            Action::Click => {
-                                w.do_callback();
-                            }
-                            Action::Focus => {
-                                let _ = w.take_focus();
-                            }
-                            Action::ReplaceSelectedText => {
-                                if let Some(ActionData::Value(s)) = req.data.clone() {
-                                    // TextEditor: operate on buffer
-                                    if utils::is_ptr_of::<text::TextEditor>(w.as_widget_ptr()) {
-                                        let mut e = text::TextEditor::from_widget_ptr(
-                                            w.as_widget_ptr() as _,
-                                        );
-                                        if let Some(mut buf) = e.buffer() {
-                                            if let Some((start, end)) = buf.selection_position() {
-                                                if start != end {
-                                                    buf.replace(start, end, &s);
-                                                    e.set_insert_position(start + s.len() as i32);
-                                                } else {
-                                                    let pos = e.insert_position();
-                                                    buf.insert(pos, &s);
-                                                    e.set_insert_position(pos + s.len() as i32);
-                                                }
-                                            } else {
-                                                let pos = e.insert_position();
-                                                buf.insert(pos, &s);
-                                                e.set_insert_position(pos + s.len() as i32);
-                                            }
-                                        }
-                                    // Input family
-                                    } else if utils::is_ptr_of::<input::Input>(w.as_widget_ptr())
-                                        || utils::is_ptr_of::<input::IntInput>(w.as_widget_ptr())
-                                        || utils::is_ptr_of::<input::FloatInput>(w.as_widget_ptr())
-                                        || utils::is_ptr_of::<input::MultilineInput>(
-                                            w.as_widget_ptr(),
-                                        )
-                                    {
-                                        let mut i =
-                                            input::Input::from_widget_ptr(w.as_widget_ptr() as _);
-                                        let start = i.position();
-                                        let end = i.mark();
-                                        if start != end {
-                                            let _ = i.replace(start, end, &s);
-                                            let _ = i.set_position(start + s.len() as i32);
-                                            let _ = i.set_mark(start + s.len() as i32);
-                                        } else {
-                                            let _ = i.insert(&s);
-                                            let new_pos = start + s.len() as i32;
-                                            let _ = i.set_position(new_pos);
-                                            let _ = i.set_mark(new_pos);
-                                        }
-                                    }
-                                }
-                            }
-                            Action::ScrollIntoView => {
-                                // Best effort: for TextEditor, ensure caret is visible
-                                if utils::is_ptr_of::<text::TextEditor>(w.as_widget_ptr()) {
-                                    let mut e =
-                                        text::TextEditor::from_widget_ptr(w.as_widget_ptr() as _);
-                                    e.show_insert_position();
-                                }
-                            }
-                            Action::ScrollToPoint => {
-                                // No robust XY->position mapping for editors; best effort noop.
-                                // Could be extended for specific widgets/containers.
-                            }
-                            Action::SetTextSelection => {
-                                if let Some(ActionData::SetTextSelection(sel)) = req.data.clone() {
-                                    // Only apply when selection nodes target this widget
-                                    if sel.anchor.node == req.target && sel.focus.node == req.target
-                                    {
-                                        // TextEditor path
-                                        if utils::is_ptr_of::<text::TextEditor>(w.as_widget_ptr()) {
-                                            let mut e = text::TextEditor::from_widget_ptr(
-                                                w.as_widget_ptr() as _,
-                                            );
-                                            let mut buf = if let Some(b) = e.buffer() {
-                                                b
-                                            } else {
-                                                let b = text::TextBuffer::default();
-                                                e.set_buffer(Some(b));
-                                                e.buffer().unwrap()
-                                            };
-                                            let len = buf.length();
-                                            let mut a = sel.anchor.character_index as i32;
-                                            let mut f = sel.focus.character_index as i32;
-                                            a = a.clamp(0, len);
-                                            f = f.clamp(0, len);
-                                            if a == f {
-                                                // Caret move
-                                                buf.unselect();
-                                                e.set_insert_position(a);
-                                            } else {
-                                                let (start, end) =
-                                                    if a <= f { (a, f) } else { (f, a) };
-                                                buf.select(start, end);
-                                                e.set_insert_position(end);
-                                            }
-                                        // Input family path
-                                        } else if utils::is_ptr_of::<input::Input>(
-                                            w.as_widget_ptr(),
-                                        ) || utils::is_ptr_of::<input::IntInput>(
-                                            w.as_widget_ptr(),
-                                        ) || utils::is_ptr_of::<input::FloatInput>(
-                                            w.as_widget_ptr(),
-                                        ) || utils::is_ptr_of::<input::MultilineInput>(
-                                            w.as_widget_ptr(),
-                                        ) {
-                                            let mut i = input::Input::from_widget_ptr(
-                                                w.as_widget_ptr() as _,
-                                            );
-                                            let len = i.value().len() as i32;
-                                            let mut a = sel.anchor.character_index as i32;
-                                            let mut f = sel.focus.character_index as i32;
-                                            a = a.clamp(0, len);
-                                            f = f.clamp(0, len);
-                                            let (start, end) = if a <= f { (a, f) } else { (f, a) };
-                                            // Set selection; on collapse, mark==position
-                                            let _ = i.set_position(start);
-                                            let _ = i.set_mark(end);
-                                        }
-                                    }
-                                }
-                            }
-                            Action::Expand => {
-                                // Expand menu-like controls
-                                if utils::is_ptr_of::<menu::MenuButton>(w.as_widget_ptr()) {
-                                    let mb =
-                                        menu::MenuButton::from_widget_ptr(w.as_widget_ptr() as _);
-                                    // Show popup; user may select an item, which will close automatically
-                                    let _ = mb.popup();
-                                }
-                                // Choice and others: no standard programmatic popup; noop.
-                            }
-                            Action::Collapse => {
-                                // No-op: popups close automatically after selection
-                            }
-                            Action::SetValue => {
-                                if let Some(data) = req.data {
-                                    match data {
-                                        ActionData::Value(s) => {
-                                            // Choice (by label)
-                                            if utils::is_ptr_of::<menu::Choice>(w.as_widget_ptr()) {
-                                                let mut c = menu::Choice::from_widget_ptr(
-                                                    w.as_widget_ptr() as _,
-                                                );
-                                                let idx = c.find_index(&s);
-                                                if idx >= 0 {
-                                                    c.set_value(idx);
-                                                }
-                                            }
-                                            // Text-capable inputs
-                                            if utils::is_ptr_of::<input::IntInput>(
-                                                w.as_widget_ptr(),
-                                            ) {
-                                                let mut i = input::IntInput::from_widget_ptr(
-                                                    w.as_widget_ptr() as _,
-                                                );
-                                                i.set_value(&s);
-                                            } else if utils::is_ptr_of::<input::FloatInput>(
-                                                w.as_widget_ptr(),
-                                            ) {
-                                                let mut i = input::FloatInput::from_widget_ptr(
-                                                    w.as_widget_ptr() as _,
-                                                );
-                                                i.set_value(&s);
-                                            } else if utils::is_ptr_of::<input::MultilineInput>(
-                                                w.as_widget_ptr(),
-                                            ) {
-                                                let mut i = input::MultilineInput::from_widget_ptr(
-                                                    w.as_widget_ptr() as _,
-                                                );
-                                                i.set_value(&s);
-                                            } else if utils::is_ptr_of::<input::Input>(
-                                                w.as_widget_ptr(),
-                                            ) {
-                                                let mut i = input::Input::from_widget_ptr(
-                                                    w.as_widget_ptr() as _,
-                                                );
-                                                i.set_value(&s);
-                                            } else if utils::is_ptr_of::<text::TextEditor>(
-                                                w.as_widget_ptr(),
-                                            ) {
-                                                let mut e = text::TextEditor::from_widget_ptr(
-                                                    w.as_widget_ptr() as _,
-                                                );
-                                                if let Some(mut buf) = e.buffer() {
-                                                    buf.set_text(&s);
-                                                } else {
-                                                    let mut buf = text::TextBuffer::default();
-                                                    buf.set_text(&s);
-                                                    e.set_buffer(Some(buf));
-                                                }
-                                            // Toggle/Check buttons (boolean from string)
-                                            } else if utils::is_ptr_of::<button::CheckButton>(
-                                                w.as_widget_ptr(),
-                                            ) {
-                                                let mut b = button::CheckButton::from_widget_ptr(
-                                                    w.as_widget_ptr() as _,
-                                                );
-                                                let on = matches!(
-                                                    s.to_ascii_lowercase().as_str(),
-                                                    "1" | "true" | "on" | "yes"
-                                                );
-                                                b.set_value(on);
-                                            } else if utils::is_ptr_of::<button::ToggleButton>(
-                                                w.as_widget_ptr(),
-                                            ) {
-                                                let mut b = button::ToggleButton::from_widget_ptr(
-                                                    w.as_widget_ptr() as _,
-                                                );
-                                                let on = matches!(
-                                                    s.to_ascii_lowercase().as_str(),
-                                                    "1" | "true" | "on" | "yes"
-                                                );
-                                                b.set_value(on);
-                                            // Valuators (parse string -> f64)
-                                            } else if let Ok(n) = s.parse::<f64>() {
-                                                macro_rules! set_val {
-                                                    ($t:ty) => {{
-                                                        if utils::is_ptr_of::<$t>(w.as_widget_ptr())
-                                                        {
-                                                            let mut v = <$t>::from_widget_ptr(
-                                                                w.as_widget_ptr() as _,
-                                                            );
-                                                            v.set_value(n);
-                                                            true
-                                                        } else {
-                                                            false
-                                                        }
-                                                    }};
-                                                }
-                                                let _handled = set_val!(valuator::Slider)
-                                                    || set_val!(valuator::NiceSlider)
-                                                    || set_val!(valuator::Dial)
-                                                    || set_val!(valuator::LineDial)
-                                                    || set_val!(valuator::Counter)
-                                                    || set_val!(valuator::Scrollbar)
-                                                    || set_val!(valuator::ValueInput)
-                                                    || set_val!(valuator::ValueOutput)
-                                                    || set_val!(valuator::ValueSlider)
-                                                    || set_val!(valuator::HorValueSlider)
-                                                    || set_val!(valuator::HorSlider)
-                                                    || set_val!(valuator::HorNiceSlider)
-                                                    || set_val!(valuator::FillSlider)
-                                                    || set_val!(valuator::HorFillSlider)
-                                                    || set_val!(misc::Spinner)
-                                                    || set_val!(misc::Progress);
-                                                // else: fallback noop
-                                            }
-                                        }
-                                        ActionData::NumericValue(n) => {
-                                            // Choice (by index)
-                                            if utils::is_ptr_of::<menu::Choice>(w.as_widget_ptr()) {
-                                                let mut c = menu::Choice::from_widget_ptr(
-                                                    w.as_widget_ptr() as _,
-                                                );
-                                                let total = c.size();
-                                                let mut idx = n.round() as i32;
-                                                if idx < 0 {
-                                                    idx = 0;
-                                                }
-                                                if idx >= total {
-                                                    idx = total - 1;
-                                                }
-                                                if total > 0 {
-                                                    c.set_value(idx);
-                                                }
-                                            }
-                                            // Inputs (apply rounding for IntInput)
-                                            if utils::is_ptr_of::<input::IntInput>(
-                                                w.as_widget_ptr(),
-                                            ) {
-                                                let mut i = input::IntInput::from_widget_ptr(
-                                                    w.as_widget_ptr() as _,
-                                                );
-                                                i.set_value(&format!("{}", n.round() as i64));
-                                            } else if utils::is_ptr_of::<input::FloatInput>(
-                                                w.as_widget_ptr(),
-                                            ) {
-                                                let mut i = input::FloatInput::from_widget_ptr(
-                                                    w.as_widget_ptr() as _,
-                                                );
-                                                i.set_value(&format!("{}", n));
-                                            } else if utils::is_ptr_of::<input::MultilineInput>(
-                                                w.as_widget_ptr(),
-                                            ) {
-                                                let mut i = input::MultilineInput::from_widget_ptr(
-                                                    w.as_widget_ptr() as _,
-                                                );
-                                                i.set_value(&format!("{}", n));
-                                            } else if utils::is_ptr_of::<input::Input>(
-                                                w.as_widget_ptr(),
-                                            ) {
-                                                let mut i = input::Input::from_widget_ptr(
-                                                    w.as_widget_ptr() as _,
-                                                );
-                                                i.set_value(&format!("{}", n));
-                                            } else if utils::is_ptr_of::<text::TextEditor>(
-                                                w.as_widget_ptr(),
-                                            ) {
-                                                let mut e = text::TextEditor::from_widget_ptr(
-                                                    w.as_widget_ptr() as _,
-                                                );
-                                                let s = format!("{}", n);
-                                                if let Some(mut buf) = e.buffer() {
-                                                    buf.set_text(&s);
-                                                } else {
-                                                    let mut buf = text::TextBuffer::default();
-                                                    buf.set_text(&s);
-                                                    e.set_buffer(Some(buf));
-                                                }
-                                            // Toggle/Check buttons (numeric → bool)
-                                            } else if utils::is_ptr_of::<button::CheckButton>(
-                                                w.as_widget_ptr(),
-                                            ) {
-                                                let mut b = button::CheckButton::from_widget_ptr(
-                                                    w.as_widget_ptr() as _,
-                                                );
-                                                b.set_value(n != 0.0);
-                                            } else if utils::is_ptr_of::<button::ToggleButton>(
-                                                w.as_widget_ptr(),
-                                            ) {
-                                                let mut b = button::ToggleButton::from_widget_ptr(
-                                                    w.as_widget_ptr() as _,
-                                                );
-                                                b.set_value(n != 0.0);
-                                            // Valuators
-                                            } else {
-                                                macro_rules! set_val {
-                                                    ($t:ty) => {{
-                                                        if utils::is_ptr_of::<$t>(w.as_widget_ptr())
-                                                        {
-                                                            let mut v = <$t>::from_widget_ptr(
-                                                                w.as_widget_ptr() as _,
-                                                            );
-                                                            v.set_value(n);
-                                                            true
-                                                        } else {
-                                                            false
-                                                        }
-                                                    }};
-                                                }
-                                                let _handled = set_val!(valuator::Slider)
-                                                    || set_val!(valuator::NiceSlider)
-                                                    || set_val!(valuator::Dial)
-                                                    || set_val!(valuator::LineDial)
-                                                    || set_val!(valuator::Counter)
-                                                    || set_val!(valuator::Scrollbar)
-                                                    || set_val!(valuator::ValueInput)
-                                                    || set_val!(valuator::ValueOutput)
-                                                    || set_val!(valuator::ValueSlider)
-                                                    || set_val!(valuator::HorValueSlider)
-                                                    || set_val!(valuator::HorSlider)
-                                                    || set_val!(valuator::HorNiceSlider)
-                                                    || set_val!(valuator::FillSlider)
-                                                    || set_val!(valuator::HorFillSlider)
-                                                    || set_val!(misc::Spinner)
-                                                    || set_val!(misc::Progress);
-                                                // else: fallback noop
-                                            }
-                                        }
-                                        _ => {}
-                                    }
-                                }
-                            }
-                            _ => {}
-                        }
-                    }
-                }
+        // Drain action requests on the UI thread, once per event loop iteration, for as
+        // long as the adapter lives.
+        let alive = Rc::downgrade(&this.adapter);
+        app::add_check(move |handle| {
+            if alive.strong_count() == 0 {
+                app::remove_check(handle);
+                return;
+            }
+            while let Some(req) = rx.recv() {
+                route(req);
             }
         });
         this
@@ -449,35 +128,40 @@ impl Adapter {
         let deactivation_handler = FltkDeactivationHandler {};
         let adapter =
             platform_adapter::Adapter::new(window, source, action_handler, deactivation_handler);
-        window.clone().resize_callback({
-            let adapter = adapter.clone();
-            move |win, _x, _y, w, h| {
-                #[cfg(not(any(target_os = "windows", target_os = "macos")))]
-                {
-                    let outer_origin = Point {
-                        x: win.x_root() as _,
-                        y: win.y_root() as _,
-                    };
-                    // Client-area origin (top-left) in root/screen coordinates
-                    let inner_origin = Point {
-                        x: win.x() as _,
-                        y: win.y() as _,
-                    };
-                    let outer_size = Size {
-                        width: win.decorated_w() as _,
-                        height: win.decorated_h() as _,
-                    };
-                    let inner_size = Size {
-                        width: w as _,
-                        height: h as _,
-                    };
-                    adapter.borrow_mut().set_root_window_bounds(
-                        Rect::from_origin_size(outer_origin, outer_size),
-                        Rect::from_origin_size(inner_origin, inner_size),
-                    );
+        #[cfg(not(any(target_os = "windows", target_os = "macos")))]
+        {
+            // Polled rather than set from a resize callback, which would replace the
+            // application's own, and since moves and scale changes don't call it anyway.
+            let alive = Rc::downgrade(&adapter);
+            let win = window.clone();
+            let mut last = None;
+            let mut report = move || {
+                let Some(adapter) = alive.upgrade().filter(|_| !win.was_deleted()) else {
+                    return false;
+                };
+                // Hidden windows have no bounds; report them again once shown
+                if !win.shown() {
+                    last = None;
+                    return true;
                 }
-            }
-        });
+                let sn = app::screen_num(win.x(), win.y());
+                let scale = app::screen_scale(sn);
+                let geometry = (win.x(), win.y(), win.w(), win.h(), scale.to_bits());
+                if last != Some(geometry) {
+                    last = Some(geometry);
+                    let (outer, inner) = root_window_bounds(&win, scale as f64);
+                    adapter.borrow_mut().set_root_window_bounds(outer, inner);
+                }
+                true
+            };
+            // Right away, so that the bounds are known from the first frame on
+            report();
+            app::add_check(move |handle| {
+                if !report() {
+                    app::remove_check(handle);
+                }
+            });
+        }
         Self { adapter }
     }
 
@@ -504,9 +188,11 @@ impl Adapter {
         unsafe { app::handle_raw(*event, window.as_widget_ptr() as _) }
     }
 
-    // pub fn update_window_focus_state(&mut self, is_focused: bool) {
-    //     self.adapter.borrow_mut().update_window_focus_state(is_focused)
-    // }
+    pub fn update_window_focus_state(&mut self, is_focused: bool) {
+        self.adapter
+            .borrow_mut()
+            .update_window_focus_state(is_focused)
+    }
 
     pub fn update_if_active(&mut self, updater: impl FnOnce() -> TreeUpdate) {
         self.adapter.borrow_mut().update_if_active(updater)
@@ -0,0 +1,176 @@
+use accesskit::NodeId;
+use fltk::{prelude::*, widget};
+use std::cell::RefCell;
+use std::collections::{HashMap, HashSet};
+
+thread_local! {
+    static IDS: RefCell<Ids> = RefCell::new(Ids::new());
+}
+
+/// Hands out node ids that stay the same for as long as a widget lives and are never
+/// reused afterwards, even when fltk allocates a new widget at the same address.
+struct Ids {
+    next: u64,
+    /// Widget address -> handle used to detect deletion, and the id it was given
+    widgets: HashMap<u64, (widget::Widget, NodeId)>,
+    /// (owner id, caller-chosen key) -> id of a synthetic child node
+    children: HashMap<(NodeId, u64), NodeId>,
+    /// Size of `widgets` at which the next sweep of deleted widgets happens
+    sweep_at: usize,
+}
+
+impl Ids {
+    fn new() -> Self {
+        Self {
+            next: 1,
+            widgets: HashMap::new(),
+            children: HashMap::new(),
+            sweep_at: 64,
+        }
+    }
+
+    fn alloc(&mut self) -> NodeId {
+        let id = NodeId(self.next);
+        self.next += 1;
+        id
+    }
+
+    fn widget(&mut self, w: &widget::Widget) -> NodeId {
+        let addr = w.as_widget_ptr() as usize as u64;
+        if let Some((h, id)) = self.widgets.get(&addr) {
+            if !h.was_deleted() {
+                return *id;
+            }
+        }
+        if self.widgets.len() >= self.sweep_at {
+            self.sweep();
+        }
+        let id = self.alloc();
+        self.widgets.insert(addr, (w.clone(), id));
+        id
+    }
+
+    fn child(&mut self, owner: NodeId, key: u64) -> NodeId {
+        if let Some(id) = self.children.get(&(owner, key)) {
+            return *id;
+        }
+        let id = self.alloc();
+        self.children.insert((owner, key), id);
+        id
+    }
+
+    /// Forget the children of `owner` that `keep` rejects, along with the children they
+    /// owned in turn.
+    fn retain_children(&mut self, owner: NodeId, keep: impl Fn(u64, NodeId) -> bool) {
+        let mut gone = Vec::new();
+        self.children.retain(|(o, key), id| {
+            let forget = *o == owner && !keep(*key, *id);
+            if forget {
+                gone.push(*id);
+            }
+            !forget
+        });
+        while !gone.is_empty() {
+            let owners: HashSet<NodeId> = gone.drain(..).collect();
+            self.children.retain(|(o, _), id| {
+                let forget = owners.contains(o);
+                if forget {
+                    gone.push(*id);
+                }
+                !forget
+            });
+        }
+    }
+
+    /// Forget deleted widgets along with the children they owned, directly or through
+    /// other children like the cells of a list entry, and their accessible properties.
+    fn sweep(&mut self) {
+        self.widgets.retain(|_, (h, _)| !h.was_deleted());
+        let mut live: HashSet<NodeId> = self.widgets.values().map(|(_, id)| *id).collect();
+        loop {
+            let owned: Vec<NodeId> = self
+                .children
+                .iter()
+                .filter(|((owner, _), id)| live.contains(owner) && !live.contains(*id))
+                .map(|(_, id)| *id)
+                .collect();
+            if owned.is_empty() {
+                break;
+            }
+            live.extend(owned);
+        }
+        self.children.retain(|(owner, _), _| live.contains(owner));
+        self.sweep_at = (self.widgets.len() * 2).max(64);
+        crate::props::prune();
+    }
+
+    fn at(&self, addr: u64) -> Option<(widget::Widget, NodeId)> {
+        self.widgets
+            .get(&addr)
+            .filter(|(h, _)| !h.was_deleted())
+            .cloned()
+    }
+
+    fn find(&self, id: NodeId) -> Option<widget::Widget> {
+        self.widgets
+            .values()
+            .find(|(h, wid)| *wid == id && !h.was_deleted())
+            .map(|(h, _)| h.clone())
+    }
+}
+
+/// The node id of `w`. Custom [`Accessible`](crate::Accessible) impls should use this
+/// for the node they return from `make_node`.
+pub fn node_id<W: WidgetExt>(w: &W) -> NodeId {
+    let w = w.as_base_widget();
+    IDS.with(|ids| ids.borrow_mut().widget(&w))
+}
+
+/// The id of a synthetic child node of `owner`, like a menu item or a list entry.
+/// `key` only has to be unique among the children of `owner`.
+pub fn child_node_id(owner: NodeId, key: u64) -> NodeId {
+    IDS.with(|ids| ids.borrow_mut().child(owner, key))
+}
+
+/// Forget the child nodes of `owner` that `keep` rejects given their key and id, so that
+/// their keys get new ids if they're used again. For children keyed by address, like
+/// menu items, which another child may get once they're gone.
+pub(crate) fn retain_children(owner: NodeId, keep: impl Fn(u64, NodeId) -> bool) {
+    IDS.with(|ids| ids.borrow_mut().retain_children(owner, keep));
+}
+
+/// The live widget that was given `id`, if any.
+pub(crate) fn widget_for(id: NodeId) -> Option<widget::Widget> {
+    IDS.with(|ids| ids.borrow().find(id))
+}
+
+/// The live widget at address `addr` and its id, if it was given one.
+pub(crate) fn widget_at(addr: usize) -> Option<(widget::Widget, NodeId)> {
+    IDS.with(|ids| ids.borrow().at(addr as u64))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use fltk::{button, frame};
+
+    #[test]
+    fn ids_outlive_their_widgets() {
+        let first = frame::Frame::default();
+        let id = node_id(&first);
+        assert_eq!(node_id(&first), id);
+        let addr = first.as_widget_ptr() as usize;
+        frame::Frame::delete(first);
+        assert!(widget_for(id).is_none());
+        assert!(widget_at(addr).is_none());
+        // Likely allocated where the first one was
+        let second = frame::Frame::default();
+        let other = node_id(&second);
+        assert_ne!(other, id);
+        assert_ne!(node_id(&button::Button::default()), id);
+        assert_eq!(
+            widget_at(second.as_widget_ptr() as usize).map(|(_, id)| id),
+            Some(other)
+        );
+    }
+}
@@ -0,0 +1,233 @@
+use accesskit::Node;
+use std::cell::Cell;
+
+thread_local! {
+    /// Whether the walk in progress reads symbols as words, see [`with_symbol_words`]
+    static SYMBOL_WORDS: Cell<bool> = const { Cell::new(false) };
+}
+
+/// Runs `f`, a walk of the widgets, with `@` symbols in labels read as words like "arrow
+/// right" when `on`, rather than dropped. The setting belongs to the context doing the
+/// walk, so it only holds for as long as `f` runs.
+pub(crate) fn with_symbol_words<R>(on: bool, f: impl FnOnce() -> R) -> R {
+    struct Restore(bool);
+    impl Drop for Restore {
+        fn drop(&mut self) {
+            SYMBOL_WORDS.with(|w| w.set(self.0));
+        }
+    }
+    let _restore = Restore(SYMBOL_WORDS.with(|w| w.replace(on)));
+    f()
+}
+
+/// A label as drawn by fltk, rather than as written.
+pub(crate) struct Label {
+    pub text: String,
+    /// The character underlined by a `&` marker
+    pub access_key: Option<char>,
+}
+
+/// Strips `&` mnemonic markers and `@` symbols from an fltk label, reading the symbols as
+/// words if `words` is set. `&&` and `@@` stand for the characters themselves.
+///
+/// Like `fl_draw` does, a symbol is only taken from the start of the label, up to the
+/// first space, and from its end, from the last `@` on. The text stops at the first other
+/// `@`. A label starting with `@.` has no symbols and is taken as it is after that.
+pub(crate) fn parse(raw: &str, words: bool) -> Label {
+    let (mut access_key, mut parts) = (None, Vec::new());
+    if let Some(rest) = raw.strip_prefix("@.") {
+        let text = strip_markers(rest, false, &mut access_key);
+        return Label { text, access_key };
+    }
+    let mut text = raw;
+    let mut start = None;
+    if let Some(code) = raw
+        .strip_prefix('@')
+        .filter(|c| !c.is_empty() && !c.starts_with('@'))
+    {
+        let (code, rest) = code.split_once(char::is_whitespace).unwrap_or((code, ""));
+        start = Some(code);
+        text = rest;
+    }
+    // The last `@` is the end symbol's, unless it's the first or second character or one
+    // of an `@@`
+    let end = text
+        .rfind('@')
+        .filter(|&i| i > 1 && !text[..i].ends_with('@'))
+        .map(|i| &text[i + 1..]);
+    let word = |code: Option<&str>| code.and_then(symbol_word).filter(|_| words);
+    parts.extend(word(start));
+    parts.push(strip_markers(text, true, &mut access_key));
+    parts.extend(word(end));
+    let text = parts
+        .iter()
+        .map(|p| p.trim())
+        .filter(|p| !p.is_empty())
+        .collect::<Vec<_>>()
+        .join(" ");
+    Label { text, access_key }
+}
+
+/// `text` without its `&` markers, noting the first one's character in `access_key`.
+/// With `symbols`, `@@` is a literal `@` and any other `@` ends the text.
+fn strip_markers(text: &str, symbols: bool, access_key: &mut Option<char>) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut chars = text.chars().peekable();
+    while let Some(c) = chars.next() {
+        match (c, chars.peek().copied()) {
+            ('&', Some('&')) => {
+                out.push(c);
+                chars.next();
+            }
+            ('&', Some(k)) => {
+                access_key.get_or_insert(k);
+            }
+            ('@', Some('@')) if symbols => {
+                out.push(c);
+                chars.next();
+            }
+            ('@', Some(_)) if symbols => break,
+            _ => out.push(c),
+        }
+    }
+    out
+}
+
+/// Sets the label of `node` from the fltk label `raw`, along with its access key.
+pub(crate) fn set_label(node: &mut Node, raw: &str) {
+    let label = parse(raw, SYMBOL_WORDS.with(|w| w.get()));
+    node.set_label(label.text);
+    if let Some(k) = label.access_key {
+        node.set_access_key(k.to_lowercase().to_string());
+    }
+}
+
+/// The word for the symbol `@code`. Codes may start with modifiers for aspect (`#`),
+/// size (`+1`, `-2`), flipping (`$`, `%`) and rotation, a keypad direction (`8` is up).
+fn symbol_word(code: &str) -> Option<String> {
+    let mut name = code.trim_start_matches(['#', '$', '%']);
+    let mut sized = name.chars();
+    if let (Some('+' | '-'), Some(d)) = (sized.next(), sized.next()) {
+        if d.is_ascii_digit() {
+            name = &name[2..];
+        }
+    }
+    let mut direction = None;
+    let mut rotated = name.chars();
+    if let (Some(d), Some(_)) = (rotated.next(), rotated.next()) {
+        if d.is_ascii_digit() {
+            direction = Some(d);
+            name = &name[1..];
+        }
+    }
+    let arrow = |default: &str| -> String {
+        let dir = match direction {
+            Some('8') => "up",
+            Some('2') => "down",
+            Some('4') => "left",
+            Some('6') => "right",
+            Some('7') => "up left",
+            Some('9') => "up right",
+            Some('1') => "down left",
+            Some('3') => "down right",
+            _ => default,
+        };
+        format!("arrow {dir}")
+    };
+    let word = match name {
+        "->" | ">" | "arrow" => arrow("right"),
+        "<-" | "<" => arrow("left"),
+        "UpArrow" => arrow("up"),
+        "DnArrow" => arrow("down"),
+        ">>" => "fast forward".into(),
+        "<<" => "rewind".into(),
+        "|>" => "play".into(),
+        ">|" => "next".into(),
+        "|<" => "previous".into(),
+        ">[]" => "play and stop".into(),
+        "[]" => "stop".into(),
+        "||" => "pause".into(),
+        "+" => "plus".into(),
+        "returnarrow" => "return".into(),
+        "square" | "circle" | "line" | "menu" | "search" | "refresh" | "reload" | "undo"
+        | "redo" => name.into(),
+        "filenew" => "new file".into(),
+        "fileopen" => "open file".into(),
+        "filesave" => "save file".into(),
+        "filesaveas" => "save file as".into(),
+        "fileprint" => "print".into(),
+        "FLTK" => "FLTK".into(),
+        _ => return None,
+    };
+    Some(word)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn mnemonics() {
+        let label = parse("&File", false);
+        assert_eq!(label.text, "File");
+        assert_eq!(label.access_key, Some('F'));
+        // Only the first marker counts
+        let label = parse("Open &Recent &Files", false);
+        assert_eq!(label.text, "Open Recent Files");
+        assert_eq!(label.access_key, Some('R'));
+        let label = parse("Save && Quit", false);
+        assert_eq!(label.text, "Save & Quit");
+        assert_eq!(label.access_key, None);
+        // A trailing marker has nothing to mark
+        assert_eq!(parse("Tom &", false).text, "Tom &");
+    }
+
+    #[test]
+    fn symbols() {
+        let text = |raw: &str, words: bool| parse(raw, words).text;
+        assert_eq!(text("@-> Next", false), "Next");
+        assert_eq!(text("Back @<-", false), "Back");
+        assert_eq!(text("@@home", false), "@home");
+        assert_eq!(text("a@@b", false), "a@b");
+        assert_eq!(text("@-> Next", true), "arrow right Next");
+        assert_eq!(text("Back @<-", true), "Back arrow left");
+        assert_eq!(text("@< Both @>", true), "arrow left Both arrow right");
+        assert_eq!(text("@+2-> Go", true), "arrow right Go");
+        assert_eq!(text("@#filesave Save", true), "save file Save");
+        assert_eq!(text("@@home", true), "@home");
+        // Unknown symbols are dropped either way
+        assert_eq!(text("@nothing Here", true), "Here");
+        // Only at the start and the end: an `@` in the middle ends the text, and what's
+        // after the last one is the end symbol
+        assert_eq!(text("Go @-> now", true), "Go");
+        assert_eq!(text("mail me@home", false), "mail me");
+        // Too close to the start for an end symbol, so it's drawn as is
+        assert_eq!(text("a@", false), "a@");
+        // `@.` turns symbols off
+        assert_eq!(text("@.@-> &Next @@", true), "@-> Next @@");
+        assert_eq!(parse("@.@-> &Next", true).access_key, Some('N'));
+    }
+
+    #[test]
+    fn symbol_words_only_hold_for_a_walk() {
+        let mut node = Node::new(accesskit::Role::Button);
+        with_symbol_words(true, || set_label(&mut node, "@-> Next"));
+        assert_eq!(node.label(), Some("arrow right Next"));
+        set_label(&mut node, "@-> Next");
+        assert_eq!(node.label(), Some("Next"));
+    }
+
+    #[test]
+    fn symbol_words() {
+        let word = |code: &str| symbol_word(code);
+        assert_eq!(word("->").as_deref(), Some("arrow right"));
+        assert_eq!(word("+2->").as_deref(), Some("arrow right"));
+        assert_eq!(word("-1>>").as_deref(), Some("fast forward"));
+        assert_eq!(word("8->").as_deref(), Some("arrow up"));
+        assert_eq!(word("#3>").as_deref(), Some("arrow down right"));
+        assert_eq!(word("$UpArrow").as_deref(), Some("arrow up"));
+        assert_eq!(word("fileopen").as_deref(), Some("open file"));
+        assert_eq!(word("9"), None);
+        assert_eq!(word("bogus"), None);
+    }
+}
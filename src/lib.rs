@@ -1,16 +1,36 @@
 #![doc = include_str!("../README.md")]
 
-use accesskit::{NodeId, TreeUpdate};
-use fltk::{enums::*, prelude::*, widget, *};
-use std::collections::HashSet;
+use accesskit::{Action, ActionRequest, NodeId, TreeUpdate};
+use fltk::{prelude::*, widget, *};
+use std::cell::{Cell, RefCell};
+use std::collections::{HashMap, HashSet};
+use std::rc::Rc;
 type ExcludePred = Box<dyn Fn(&widget::Widget) -> bool>;
 
 pub mod accessible;
+mod browsers;
+mod changes;
 mod fltk_adapter;
+mod ids;
+mod label;
+mod menus;
 mod platform_adapter;
+mod popup;
+mod props;
+mod registry;
+mod shortcut;
+mod tabs;
+mod targets;
 
 pub use accessible::Accessible;
+pub use changes::{dispatch, Invalidator};
 pub use fltk_adapter::Adapter;
+pub use ids::{child_node_id, node_id};
+pub use props::{props, PropsMut};
+
+use changes::{Changes, Refresh};
+use registry::Registry;
+use targets::{Target, Targets};
 
 #[derive(Default)]
 pub struct Excludes {
@@ -41,6 +61,8 @@ impl Excludes {
 pub struct AccessibilityBuilder {
     root: window::Window,
     excludes: Excludes,
+    registry: Registry,
+    symbol_words: bool,
 }
 
 impl AccessibilityBuilder {
@@ -48,6 +70,8 @@ impl AccessibilityBuilder {
         Self {
             root,
             excludes: Excludes::default(),
+            registry: Registry::default(),
+            symbol_words: false,
         }
     }
     pub fn exclude_widget<W: WidgetExt>(mut self, w: &W) -> Self {
@@ -70,18 +94,56 @@ impl AccessibilityBuilder {
         self.excludes.preds.push(Box::new(pred));
         self
     }
+    /// Use `acc` to describe this widget instance instead of the built-in impl.
+    /// Useful for widgets made with `widget_extends!`, which fltk sees as their base type.
+    pub fn register_widget<W: WidgetExt>(mut self, w: &W, acc: Box<dyn Accessible>) -> Self {
+        self.registry.register_widget(w, acc);
+        self
+    }
+    /// Describe every widget tagged with the type key `T` (see [`Self::tag_widget`])
+    /// using the `Accessible` impl returned by `factory`.
+    pub fn register_type<T: 'static>(
+        mut self,
+        factory: impl Fn(&widget::Widget) -> Box<dyn Accessible> + 'static,
+    ) -> Self {
+        self.registry.register_type::<T>(factory);
+        self
+    }
+    /// Tag this widget instance with the type key `T`.
+    pub fn tag_widget<T: 'static, W: WidgetExt>(mut self, w: &W) -> Self {
+        self.registry.tag_widget::<T, W>(w);
+        self
+    }
+    /// Read `@` symbols in labels as words like "arrow right" or "save file", instead of
+    /// leaving them out.
+    pub fn symbol_words(mut self, on: bool) -> Self {
+        self.symbol_words = on;
+        self
+    }
+    /// Describe this widget with `description` instead of its tooltip.
+    /// Same as `props(w).description(description)`, see [`props`].
+    pub fn describe_widget<W: WidgetExt>(self, w: &W, description: &str) -> Self {
+        props(w).description(description);
+        self
+    }
+    /// Exposes the root window, and any other top-level window once it's shown.
     pub fn attach(self) -> AccessibilityContext {
-        let mut wids = collect_nodes(&self.root, &self.excludes);
-        let (win_id, win_node) = self
-            .root
-            .make_node(&wids.iter().map(|x| x.0).collect::<Vec<_>>());
-        wids.push((win_id, win_node));
-        let activation_handler = crate::fltk_adapter::FltkActivationHandler { wids, win_id };
-        let adapter = Adapter::new(&self.root, activation_handler);
+        let registry = Rc::new(RefCell::new(self.registry));
+        let changes = Rc::new(Changes::default());
+        let walker = Walker {
+            excludes: &self.excludes,
+            registry: &registry.borrow(),
+            symbol_words: self.symbol_words,
+        };
+        let main = WindowTree::new(self.root, &walker, &registry, &changes);
         AccessibilityContext {
-            adapter,
-            root: self.root,
+            main,
+            others: RefCell::new(Vec::new()),
             excludes: self.excludes,
+            registry,
+            symbol_words: self.symbol_words,
+            changes,
+            focused_window: Cell::new(0),
         }
     }
 }
@@ -90,88 +152,598 @@ pub fn builder(root: window::Window) -> AccessibilityBuilder {
     AccessibilityBuilder::new(root)
 }
 
-pub struct AccessibilityContext {
+/// The tree of a top-level window and the adapter it's sent to.
+struct WindowTree {
     adapter: Adapter,
     root: window::Window,
+    /// Address and node id of `root`, which can't be asked for once it's deleted
+    addr: usize,
+    win_id: NodeId,
+    targets: Rc<RefCell<Targets>>,
+    /// The nodes as of the last update, to only send what changed since
+    sent: RefCell<HashMap<NodeId, accesskit::Node>>,
+}
+
+impl WindowTree {
+    fn new(
+        root: window::Window,
+        walker: &Walker,
+        registry: &Rc<RefCell<Registry>>,
+        changes: &Rc<Changes>,
+    ) -> Self {
+        let mut targets = Targets::default();
+        let (win_id, wids) = walker.collect(&root, &mut targets);
+        // What the activation handler sends is what assistive technologies start from
+        let sent = wids.iter().cloned().collect();
+        let focus = app::focus()
+            .map(|focused| node_id(&focused))
+            .filter(|id| targets.contains(*id))
+            .unwrap_or(win_id);
+        let activation_handler = crate::fltk_adapter::FltkActivationHandler {
+            wids,
+            win_id,
+            focus,
+        };
+        let targets = Rc::new(RefCell::new(targets));
+        let adapter = Adapter::with_router(&root, activation_handler, {
+            let registry = registry.clone();
+            let targets = targets.clone();
+            let invalidator = changes.invalidator();
+            move |req| route(&req, &targets, &registry, &invalidator)
+        });
+        Self {
+            adapter,
+            addr: root.as_widget_ptr() as usize,
+            win_id,
+            root,
+            targets,
+            sent: RefCell::new(sent),
+        }
+    }
+
+    /// Whether `win`, a live window, is this tree's root.
+    fn is(&self, win: &window::Window) -> bool {
+        !self.root.was_deleted() && self.addr == win.as_widget_ptr() as usize
+    }
+
+    /// Walks the widgets and returns an update holding only the nodes that were added or
+    /// changed since the previous one. The walk's ids become the valid action targets.
+    fn tree_update(&self, walker: &Walker) -> TreeUpdate {
+        if self.root.was_deleted() {
+            return self.empty_update();
+        }
+        let mut targets = Targets::default();
+        let (win_id, wids) = walker.collect(&self.root, &mut targets);
+        *self.targets.borrow_mut() = targets;
+        let focus = self.focus(win_id);
+        let mut sent = self.sent.borrow_mut();
+        let mut current = HashMap::with_capacity(wids.len());
+        let mut nodes = Vec::new();
+        for (id, node) in wids {
+            if sent.get(&id) != Some(&node) {
+                nodes.push((id, node.clone()));
+            }
+            current.insert(id, node);
+        }
+        // Nodes that are gone drop out of the cache, so they're sent again if they return
+        *sent = current;
+        TreeUpdate {
+            nodes,
+            tree: None,
+            focus,
+        }
+    }
+
+    /// Like [`Self::tree_update`], rebuilding only the nodes of the widgets at `addrs`
+    /// instead of walking all of them. Their accessible descendants are assumed to be
+    /// unchanged. Widgets that weren't part of the last update, are hidden now or whose
+    /// children changed take a full update, since nodes appear, go or move.
+    fn widgets_update(&self, walker: &Walker, addrs: &[usize]) -> TreeUpdate {
+        if self.root.was_deleted() {
+            return self.empty_update();
+        }
+        match self.rebuild(walker, addrs) {
+            Some(update) => update,
+            None => self.tree_update(walker),
+        }
+    }
+
+    /// The update of [`Self::widgets_update`], or `None` if it takes a full one. Nothing
+    /// is recorded as sent until every widget is known not to.
+    fn rebuild(&self, walker: &Walker, addrs: &[usize]) -> Option<TreeUpdate> {
+        let mut targets = Targets::default();
+        let mut rebuilt = Vec::new();
+        {
+            let sent = self.sent.borrow();
+            let known = self.targets.borrow();
+            for addr in addrs {
+                // The window's node isn't one of its widgets'
+                if *addr == self.addr {
+                    return None;
+                }
+                let (w, id) = ids::widget_at(*addr)?;
+                if known.widget(id).is_none() {
+                    // Widgets of other windows are theirs to update
+                    let top = w.top_window().map(|t| t.as_widget_ptr() as usize);
+                    if top == Some(self.addr) {
+                        return None;
+                    }
+                    continue;
+                }
+                let old = sent.get(&id)?;
+                if !w.visible() {
+                    return None;
+                }
+                let before: Vec<NodeId> = old
+                    .children()
+                    .iter()
+                    .copied()
+                    .filter(|c| known.widget(*c).is_some())
+                    .collect();
+                let children = match w.as_group() {
+                    Some(grp) if !accessible::is_composite(&w) => {
+                        let mut scratch = Targets::default();
+                        let now = walker.walk_group(&grp, &mut Vec::new(), &mut scratch);
+                        if now != before {
+                            return None;
+                        }
+                        now
+                    }
+                    _ => before,
+                };
+                let nodes = walker.widget_nodes(&w, &children, &mut targets);
+                if nodes.last().map(|(id, _)| *id) != Some(id) {
+                    return None;
+                }
+                rebuilt.push((synthetic_below(&sent, &known, id), nodes));
+            }
+        }
+        let mut sent = self.sent.borrow_mut();
+        let mut known = self.targets.borrow_mut();
+        let mut changed = Vec::new();
+        for (old, nodes) in rebuilt {
+            // Items and lines that are gone, so they're sent again if they return
+            for id in old.iter().filter(|id| !nodes.iter().any(|(n, _)| n == *id)) {
+                sent.remove(id);
+                known.remove(*id);
+            }
+            for (id, node) in nodes {
+                if sent.get(&id) != Some(&node) {
+                    changed.push((id, node.clone()));
+                    sent.insert(id, node);
+                }
+            }
+        }
+        known.extend(targets);
+        drop(known);
+        Some(TreeUpdate {
+            nodes: changed,
+            tree: None,
+            focus: self.focus(self.win_id),
+        })
+    }
+
+    /// An update that changes nothing, for when the main window was deleted while the
+    /// context lives.
+    fn empty_update(&self) -> TreeUpdate {
+        TreeUpdate {
+            nodes: Vec::new(),
+            tree: None,
+            focus: self.win_id,
+        }
+    }
+
+    /// The focused widget's id, or `win_id` when the focus isn't part of this tree because
+    /// it's in another window, excluded or belongs to a composite widget. While a popup
+    /// menu is open, its highlighted item has the focus.
+    fn focus(&self, win_id: NodeId) -> NodeId {
+        let targets = self.targets.borrow();
+        popup::highlighted()
+            .map(|(menu, item)| child_node_id(node_id(&menu), item as u64))
+            .filter(|id| targets.contains(*id))
+            .or_else(|| {
+                fltk::app::focus()
+                    .map(|focused| node_id(&focused))
+                    .filter(|id| targets.contains(*id))
+            })
+            .unwrap_or(win_id)
+    }
+}
+
+/// The nodes below `id` in `sent` that don't stand for widgets, like menu items and list
+/// entries, and what's below them in turn.
+fn synthetic_below(
+    sent: &HashMap<NodeId, accesskit::Node>,
+    targets: &Targets,
+    id: NodeId,
+) -> Vec<NodeId> {
+    let mut below = Vec::new();
+    let mut stack = vec![id];
+    while let Some(id) = stack.pop() {
+        let Some(node) = sent.get(&id) else {
+            continue;
+        };
+        for c in node.children() {
+            if targets.widget(*c).is_none() {
+                below.push(*c);
+                stack.push(*c);
+            }
+        }
+    }
+    below
+}
+
+/// Performs `req` on its target, if it's one we exposed in the last update and that still
+/// exists, and asks for the update showing what the action did.
+///
+/// Requests are routed from a check, and fltk runs the newest check first, so the one
+/// sending updates may already be done for this iteration. Waking the event loop makes the
+/// next iteration come right away instead of with the next unrelated event.
+fn route(
+    req: &ActionRequest,
+    targets: &RefCell<Targets>,
+    registry: &RefCell<Registry>,
+    invalidator: &Invalidator,
+) {
+    let target = targets.borrow().resolve(req.target, req.action);
+    match target {
+        Some(Target::Widget(w)) => {
+            let custom = registry.borrow().accessible(&w);
+            accessible::perform_action(&w, custom, req);
+        }
+        // Click is what accesskit calls the default action, the one screen readers
+        // perform to activate an item
+        Some(Target::MenuItem { menu, item }) => {
+            if req.action == Action::Click {
+                crate::menus::pick(&menu, item);
+            }
+        }
+        Some(Target::BrowserLine { browser, line }) => {
+            crate::browsers::perform_line_action(&browser, line, req);
+        }
+        Some(Target::Tab { tabs, panel }) => match req.action {
+            Action::Click => crate::tabs::select(&tabs, &panel, false),
+            Action::Focus => crate::tabs::select(&tabs, &panel, true),
+            _ => (),
+        },
+        None => (),
+    }
+    invalidator.invalidate();
+}
+
+/// Exposes the widgets of an application's top-level windows, each to its own adapter.
+pub struct AccessibilityContext {
+    /// The window given to the builder, exposed for as long as the context lives
+    main: WindowTree,
+    /// The other top-level windows that are currently shown
+    others: RefCell<Vec<WindowTree>>,
     excludes: Excludes,
+    registry: Rc<RefCell<Registry>>,
+    symbol_words: bool,
+    changes: Rc<Changes>,
+    /// Address of the window holding the focus as of the last update, 0 for none
+    focused_window: Cell<usize>,
 }
 
 impl AccessibilityContext {
-    fn collect(&self) -> Vec<(NodeId, accesskit::Node)> {
-        let mut wids = collect_nodes(&self.root, &self.excludes);
-        let (win_id, win_node) = self
-            .root
-            .make_node(&wids.iter().map(|x| x.0).collect::<Vec<_>>());
-        wids.push((win_id, win_node));
-        wids
+    /// Same as [`AccessibilityBuilder::register_widget`], for widgets created after attaching.
+    pub fn register_widget<W: WidgetExt>(&mut self, w: &W, acc: Box<dyn Accessible>) {
+        self.registry.borrow_mut().register_widget(w, acc);
+    }
+    /// Same as [`AccessibilityBuilder::register_type`].
+    pub fn register_type<T: 'static>(
+        &mut self,
+        factory: impl Fn(&widget::Widget) -> Box<dyn Accessible> + 'static,
+    ) {
+        self.registry.borrow_mut().register_type::<T>(factory);
+    }
+    /// Same as [`AccessibilityBuilder::tag_widget`], for widgets created after attaching.
+    pub fn tag_widget<T: 'static, W: WidgetExt>(&mut self, w: &W) {
+        self.registry.borrow_mut().tag_widget::<T, W>(w);
+    }
+    /// Same as [`AccessibilityBuilder::describe_widget`], for widgets created after attaching.
+    pub fn describe_widget<W: WidgetExt>(&mut self, w: &W, description: &str) {
+        props(w).description(description);
+        self.invalidate();
+    }
+    /// Requests an update, sent once when the current event loop iteration is done.
+    /// Events that may change widgets already do this; call it after changing widgets
+    /// from elsewhere. Once [`AccessibleApp::run_with_accessibility`] owns the context,
+    /// timeouts and idle callbacks do this through an [`Invalidator`] instead.
+    pub fn invalidate(&self) {
+        self.changes.invalidate();
+    }
+    /// A handle to request updates from other threads, see [`Invalidator`].
+    pub fn invalidator(&self) -> Invalidator {
+        self.changes.invalidator()
+    }
+    /// Sends an update if anything was invalidated or the focus moved since the last one.
+    fn flush(&self) {
+        popup::sync();
+        self.sync_windows();
+        let refresh = self.changes.take();
+        let walker = Walker {
+            excludes: &self.excludes,
+            registry: &self.registry.borrow(),
+            symbol_words: self.symbol_words,
+        };
+        let others = self.others.borrow();
+        let trees = std::iter::once(&self.main).chain(others.iter());
+        match refresh {
+            Refresh::None => (),
+            Refresh::Full => {
+                for tree in trees {
+                    let mut adapter = tree.adapter.clone();
+                    adapter.update_if_active(|| tree.tree_update(&walker));
+                }
+            }
+            Refresh::Widgets(addrs) => {
+                for tree in trees {
+                    let mut adapter = tree.adapter.clone();
+                    adapter.update_if_active(|| tree.widgets_update(&walker, &addrs));
+                }
+            }
+        }
+        self.sync_window_focus();
+    }
+    /// Gives every shown top-level window other than the main one an adapter, and drops
+    /// those of windows that were hidden or deleted. Menus and tooltips are left out, as
+    /// they're exposed through the widgets they belong to, and so are subwindows, which
+    /// fltk lists too but are part of their top-level window's tree.
+    fn sync_windows(&self) {
+        let shown: Vec<window::Window> = app::windows()
+            .unwrap_or_default()
+            .iter()
+            .filter_map(window::Window::from_dyn_widget)
+            .filter(|w| {
+                w.parent().is_none()
+                    && !w.is_override()
+                    && !popup::is_menu_window(w)
+                    && !self.main.is(w)
+                    && !self.excludes.skip_subtree(&w.as_base_widget())
+            })
+            .collect();
+        let walker = Walker {
+            excludes: &self.excludes,
+            registry: &self.registry.borrow(),
+            symbol_words: self.symbol_words,
+        };
+        let mut others = self.others.borrow_mut();
+        // Deleted windows go first, as their handles can't be used anymore
+        others.retain(|t| !t.root.was_deleted() && shown.iter().any(|w| t.is(w)));
+        for w in shown {
+            if !others.iter().any(|t| t.is(&w)) {
+                let tree = WindowTree::new(w, &walker, &self.registry, &self.changes);
+                others.push(tree);
+            }
+        }
+    }
+    /// Tells the adapters which of their windows holds the focus, where the platform
+    /// doesn't track it by itself.
+    fn sync_window_focus(&self) {
+        let focused = app::focus()
+            .and_then(|f| f.top_window())
+            .map_or(0, |w| w.as_widget_ptr() as usize);
+        let before = self.focused_window.replace(focused);
+        if before == focused {
+            return;
+        }
+        let others = self.others.borrow();
+        for tree in std::iter::once(&self.main).chain(others.iter()) {
+            if tree.root.was_deleted() {
+                continue;
+            }
+            if tree.addr == before || tree.addr == focused {
+                tree.adapter
+                    .clone()
+                    .update_window_focus_state(tree.addr == focused);
+            }
+        }
+    }
+    /// Walks the main window's widgets and returns an update holding only the nodes that
+    /// were added or changed since the previous one. A parent whose children changed is
+    /// itself changed.
+    ///
+    /// [`update_focused`] sends this to the adapter; only call it to feed your own adapter,
+    /// since whatever it returns is assumed to have been sent.
+    pub fn tree_update(&self) -> TreeUpdate {
+        self.main.tree_update(&Walker {
+            excludes: &self.excludes,
+            registry: &self.registry.borrow(),
+            symbol_words: self.symbol_words,
+        })
     }
 }
 
 pub trait AccessibleApp {
+    /// Runs the app, keeping the adapters up to date. This installs an event dispatch
+    /// function, see [`dispatch`] to install one of your own alongside it.
     fn run_with_accessibility(&self, ac: AccessibilityContext) -> Result<(), FltkError>;
 }
 
+/// Sends the changes of every exposed window to its adapter.
 pub fn update_focused(ac: &AccessibilityContext) {
-    let mut adapter = ac.adapter.clone();
-    let wids = ac.collect();
-    if let Some(focused) = fltk::app::focus() {
-        let node_id = NodeId(focused.as_widget_ptr() as _);
-        adapter.update_if_active(|| TreeUpdate {
-            nodes: wids,
-            tree: None,
-            focus: node_id,
-        });
-    }
+    ac.invalidate();
+    ac.flush();
 }
 
 impl AccessibleApp for app::App {
     fn run_with_accessibility(&self, ac: AccessibilityContext) -> Result<(), FltkError> {
-        // Move context into the handler, using a cloned root to register the closure.
-        let ctx = ac;
-        let mut root = ctx.root.clone();
-        root.handle({
-            move |_, ev| match ev {
-                Event::KeyUp => {
-                    update_focused(&ctx);
-                    false
-                }
-                _ => false,
-            }
-        });
+        // Events only note that something may have changed; the check runs after the
+        // events of an iteration are handled and sends at most one update for them.
+        let ctx = Rc::new(ac);
+        changes::observe(&ctx.changes);
+        app::add_check(move |_| ctx.flush());
         self.run()
     }
 }
 
-fn collect_nodes(root: &window::Window, excludes: &Excludes) -> Vec<(NodeId, accesskit::Node)> {
-    let mut out = Vec::new();
-    // Traverse children of root
-    let root_w = root.as_base_widget();
-    if let Some(grp) = root_w.as_group() {
-        walk_group(&grp, excludes, &mut out);
-    }
-    out
+struct Walker<'a> {
+    excludes: &'a Excludes,
+    registry: &'a Registry,
+    /// Whether labels read their `@` symbols as words, see
+    /// [`AccessibilityBuilder::symbol_words`]
+    symbol_words: bool,
 }
 
-fn walk_group(grp: &group::Group, excludes: &Excludes, out: &mut Vec<(NodeId, accesskit::Node)>) {
-    for i in 0..grp.children() {
-        if let Some(child) = grp.child(i) {
-            if excludes.skip_subtree(&child) {
-                continue;
-            }
-            // If the child is excluded and it's a group, skip its entire subtree.
-            // If it's excluded and not a group, just skip the node.
-            let subgrp = child.as_group();
-            if excludes.matches(&child) {
-                // For groups this prevents iterating children.
-                continue;
-            }
-            // Add node(s) if supported (some widgets expand to multiple nodes)
-            let nodes = crate::accessible::nodes_for_widget(&child);
-            out.extend(nodes);
-            // Recurse into groups that weren't excluded
-            if let Some(subgrp) = subgrp {
-                walk_group(&subgrp, excludes, out);
+impl Walker<'_> {
+    /// Builds the nodes for `root` and everything below it. The root window's node is
+    /// the last entry of the returned list.
+    /// `targets` receives what each node stands for.
+    fn collect(
+        &self,
+        root: &window::Window,
+        targets: &mut Targets,
+    ) -> (NodeId, Vec<(NodeId, accesskit::Node)>) {
+        label::with_symbol_words(self.symbol_words, || self.collect_nodes(root, targets))
+    }
+
+    fn collect_nodes(
+        &self,
+        root: &window::Window,
+        targets: &mut Targets,
+    ) -> (NodeId, Vec<(NodeId, accesskit::Node)>) {
+        let mut out = Vec::new();
+        let root_w = root.as_base_widget();
+        let children = match root_w.as_group() {
+            Some(grp) => self.walk_group(&grp, &mut out, targets),
+            None => Vec::new(),
+        };
+        let (win_id, win_node) = self
+            .registry
+            .make_node(&root_w, &children)
+            .unwrap_or_else(|| {
+                let (id, mut node) = root.make_node(&children);
+                props::apply(&root_w, &mut node);
+                (id, node)
+            });
+        targets.insert_widget(win_id, &root_w);
+        out.push((win_id, win_node));
+        (win_id, out)
+    }
+
+    /// Appends the nodes of `grp`'s descendants to `out` and returns the ids that belong
+    /// directly under `grp`'s own node.
+    fn walk_group(
+        &self,
+        grp: &group::Group,
+        out: &mut Vec<(NodeId, accesskit::Node)>,
+        targets: &mut Targets,
+    ) -> Vec<NodeId> {
+        let mut ids = Vec::new();
+        for i in 0..grp.children() {
+            if let Some(child) = grp.child(i) {
+                // Hidden widgets, like the unselected tabs of a Tabs, aren't on screen
+                if self.excludes.skip_subtree(&child) || !child.visible() {
+                    continue;
+                }
+                // Composite widgets (text displays, tables, browsers...) own their
+                // scrollbars and inputs as implementation details, so don't descend.
+                let children = match child.as_group() {
+                    Some(subgrp) if !crate::accessible::is_composite(&child) => {
+                        self.walk_group(&subgrp, out, targets)
+                    }
+                    _ => Vec::new(),
+                };
+                // Excluded widgets and groups without semantics are transparent:
+                // their accessible descendants are hoisted into our list.
+                if self.excludes.matches(&child) {
+                    ids.extend(children);
+                    continue;
+                }
+                // The widget's own node is always the last one returned
+                let nodes = self.widget_nodes(&child, &children, targets);
+                match nodes.last() {
+                    Some((id, _)) => {
+                        targets.insert_widget(*id, &child);
+                        ids.push(*id);
+                        out.extend(nodes);
+                    }
+                    None => ids.extend(children),
+                }
             }
         }
+        ids
+    }
+
+    /// The nodes of `w`, its own node last. Custom impls take precedence over the
+    /// built-in ones.
+    fn widget_nodes(
+        &self,
+        w: &widget::Widget,
+        children: &[NodeId],
+        targets: &mut Targets,
+    ) -> Vec<(NodeId, accesskit::Node)> {
+        label::with_symbol_words(self.symbol_words, || {
+            let mut nodes = match self.registry.make_node(w, children) {
+                Some(node) => vec![node],
+                None => crate::accessible::expand_widget(w, children, targets),
+            };
+            crate::accessible::apply_state(w, &mut nodes);
+            nodes
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn routed_actions_are_sent() {
+        let changes = Changes::default();
+        let registry = RefCell::new(Registry::default());
+        let mut button = button::Button::default();
+        let clicked = Rc::new(Cell::new(false));
+        button.set_callback({
+            let clicked = clicked.clone();
+            move |_| clicked.set(true)
+        });
+        let id = node_id(&button);
+        let mut targets = Targets::default();
+        targets.insert_widget(id, &button.as_base_widget());
+        let targets = RefCell::new(targets);
+        // Nothing is due yet, the update for the action has to be asked for by routing it
+        changes.take();
+        assert!(matches!(changes.take(), Refresh::None));
+        let req = ActionRequest {
+            action: Action::Click,
+            target: id,
+            data: None,
+        };
+        route(&req, &targets, &registry, &changes.invalidator());
+        assert!(clicked.get());
+        assert!(matches!(changes.take(), Refresh::Full));
+    }
+
+    #[test]
+    fn picked_menu_items_are_sent() {
+        let changes = Changes::default();
+        let registry = RefCell::new(Registry::default());
+        let picked = Rc::new(Cell::new(false));
+        let mut bar = menu::MenuBar::default();
+        bar.add(
+            "&File/&Open",
+            enums::Shortcut::None,
+            menu::MenuFlag::Normal,
+            {
+                let picked = picked.clone();
+                move |_| picked.set(true)
+            },
+        );
+        let item = bar.find_item("&File/&Open").unwrap();
+        let id = child_node_id(node_id(&bar), unsafe { item.as_ptr() } as u64);
+        let mut targets = Targets::default();
+        targets.insert_menu_item(id, &bar, &item);
+        let targets = RefCell::new(targets);
+        changes.take();
+        let req = ActionRequest {
+            action: Action::Click,
+            target: id,
+            data: None,
+        };
+        route(&req, &targets, &registry, &changes.invalidator());
+        assert!(picked.get());
+        assert!(matches!(changes.take(), Refresh::Full));
     }
 }
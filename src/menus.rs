@@ -0,0 +1,568 @@
+use accesskit::{Action, Node, NodeId, Role, Toggled};
+use fltk::{
+    enums::CallbackTrigger,
+    menu::{self, MenuFlag},
+    prelude::*,
+    widget,
+};
+use std::collections::HashSet;
+use std::os::raw::{c_char, c_int, c_uchar, c_uint, c_void};
+
+use crate::ids::{child_node_id, retain_children};
+use crate::label::set_label;
+use crate::shortcut::set_shortcut;
+use crate::targets::Targets;
+
+/// fltk's `Fl_Menu_Item`, which is a plain struct that applications define static arrays
+/// of, so its layout doesn't change. fltk-rs doesn't expose the shortcut, the flags of
+/// items outside of a menu's own array, nor whether there's a callback.
+#[repr(C)]
+struct MenuItemRepr {
+    _text: *const c_char,
+    shortcut: c_int,
+    callback: *const c_void,
+    user_data: *mut c_void,
+    flags: c_int,
+    _labeltype: c_uchar,
+    _labelfont: c_int,
+    _labelsize: c_int,
+    _labelcolor: c_uint,
+}
+
+fn head(item: &menu::MenuItem) -> &MenuItemRepr {
+    unsafe { &*(item.as_ptr() as *const MenuItemRepr) }
+}
+
+fn flags_of(item: &menu::MenuItem) -> MenuFlag {
+    MenuFlag::from_bits_retain(head(item).flags)
+}
+
+/// The item `i` places after `first` in its array. Arrays end with an item without a
+/// label, as do the submenus in them.
+fn item_at(first: &menu::MenuItem, i: i32) -> menu::MenuItem {
+    let p = unsafe { (first.as_ptr() as *mut MenuItemRepr).add(i as usize) };
+    unsafe { menu::MenuItem::from_ptr(p as _) }
+}
+
+/// Submenu pointers can point back at a menu they're in, so they're only followed this
+/// many times.
+const MAX_DEPTH: usize = 16;
+
+/// The first item of the array that `item` points to, if it's a submenu pointer.
+fn pointed(item: &menu::MenuItem) -> Option<menu::MenuItem> {
+    let h = head(item);
+    (flags_of(item).contains(MenuFlag::SubmenuPointer) && !h.user_data.is_null())
+        .then(|| unsafe { menu::MenuItem::from_ptr(h.user_data as _) })
+}
+
+/// The first item of `menu`'s array, unless it has none.
+fn first_item<M: MenuExt>(menu: &M) -> Option<menu::MenuItem> {
+    menu.menu().filter(|_| menu.size() > 0)
+}
+
+/// Moves `i` past the items of the submenu that ends there, and past its end.
+fn skip_level(first: &menu::MenuItem, i: &mut i32) {
+    let mut nested = 0;
+    loop {
+        let item = item_at(first, *i);
+        *i += 1;
+        if item.label().is_none() {
+            if nested == 0 {
+                return;
+            }
+            nested -= 1;
+        } else if flags_of(&item).contains(MenuFlag::Submenu) {
+            nested += 1;
+        }
+    }
+}
+
+/// Builds the nodes of a menu's items, following the nesting of its submenus. Shared by
+/// menu bars, menu buttons and choices.
+pub(crate) struct MenuWalker<'a, M> {
+    pub menu: &'a M,
+    /// The node the items belong to, which their ids derive from
+    pub owner: NodeId,
+    /// The role of the items that aren't submenus
+    pub item_role: Role,
+    /// Address of the item to mark selected, like a choice's current one
+    pub chosen: Option<usize>,
+}
+
+impl<M: MenuExt> MenuWalker<'_, M> {
+    /// Appends the nodes of every item to `out` and returns the ids of the top-level ones.
+    pub(crate) fn walk(&self, out: &mut Vec<(NodeId, Node)>, targets: &mut Targets) -> Vec<NodeId> {
+        let start = out.len();
+        let ids = match first_item(self.menu) {
+            Some(first) => {
+                // The submenus whose menus are open in the popup
+                let open = crate::popup::shown(&self.menu.as_base_widget())
+                    .map(|s| s.submenus)
+                    .unwrap_or_default();
+                self.level(&first, &mut 0, &open, 0, out, targets)
+            }
+            None => Vec::new(),
+        };
+        // Items removed since the last walk lose their ids, so that an item added at
+        // the address of one of them doesn't get its id. 0 is the popup of choices and
+        // menu buttons.
+        let used: HashSet<NodeId> = out[start..].iter().map(|(id, _)| *id).collect();
+        retain_children(self.owner, |key, id| key == 0 || used.contains(&id));
+        ids
+    }
+
+    /// The items of the array starting at `first`, from index `i` up to the end of their
+    /// level, which is marked by an item without a label. Leaves `i` past that item.
+    /// `depth` is how many submenu pointers were followed to get there.
+    fn level(
+        &self,
+        first: &menu::MenuItem,
+        i: &mut i32,
+        open: &[usize],
+        depth: usize,
+        out: &mut Vec<(NodeId, Node)>,
+        targets: &mut Targets,
+    ) -> Vec<NodeId> {
+        let mut level: Vec<(NodeId, Node)> = Vec::new();
+        // The radio group of each node in `level`, numbered in order
+        let mut groups: Vec<Option<usize>> = Vec::new();
+        let mut open_group = None;
+        let mut group_count = 0;
+        loop {
+            let item = item_at(first, *i);
+            *i += 1;
+            let Some(label) = item.label() else {
+                break;
+            };
+            let flags = flags_of(&item);
+            let visible = item.visible();
+            // A submenu's items follow it in the array, so they're gone through even when
+            // it's hidden. A submenu pointer's are in an array of their own.
+            let children = if flags.contains(MenuFlag::Submenu) {
+                if visible {
+                    self.level(first, i, open, depth, out, targets)
+                } else {
+                    skip_level(first, i);
+                    Vec::new()
+                }
+            } else if let Some(sub) = pointed(&item).filter(|_| visible && depth < MAX_DEPTH) {
+                self.level(&sub, &mut 0, open, depth + 1, out, targets)
+            } else {
+                Vec::new()
+            };
+            // Like `Fl_Menu_Item::setonly`, a radio group is a run of radio items that
+            // ends at any other item or at a divider, hidden items included
+            let radio = flags.contains(MenuFlag::Radio);
+            let group = radio.then(|| {
+                *open_group.get_or_insert_with(|| {
+                    group_count += 1;
+                    group_count
+                })
+            });
+            open_group = group.filter(|_| !flags.contains(MenuFlag::MenuDivider));
+            let addr = unsafe { item.as_ptr() } as usize;
+            if !visible {
+                // Its divider still separates the items around it
+                if flags.contains(MenuFlag::MenuDivider) {
+                    level.push(self.separator(addr));
+                    groups.push(None);
+                }
+                continue;
+            }
+            let submenu = flags.intersects(MenuFlag::Submenu | MenuFlag::SubmenuPointer);
+            let role = if submenu {
+                Role::Menu
+            } else if self.item_role != Role::MenuItem {
+                self.item_role
+            } else if radio {
+                Role::MenuItemRadio
+            } else if flags.contains(MenuFlag::Toggle) {
+                Role::MenuItemCheckBox
+            } else {
+                Role::MenuItem
+            };
+            let mut node = Node::new(role);
+            set_label(&mut node, &label);
+            set_shortcut(&mut node, head(&item).shortcut);
+            if matches!(role, Role::MenuItemRadio | Role::MenuItemCheckBox) {
+                node.set_toggled(if item.value() {
+                    Toggled::True
+                } else {
+                    Toggled::False
+                });
+            }
+            if submenu {
+                node.set_expanded(open.contains(&addr));
+            }
+            if self.chosen == Some(addr) {
+                node.set_selected(true);
+            }
+            if !item.active() {
+                node.set_disabled();
+            } else if !submenu {
+                node.add_action(Action::Click);
+            }
+            for c in &children {
+                node.push_child(*c);
+            }
+            let id = child_node_id(self.owner, addr as u64);
+            targets.insert_menu_item(id, self.menu, &item);
+            level.push((id, node));
+            groups.push(group.filter(|_| role == Role::MenuItemRadio));
+            if flags.contains(MenuFlag::MenuDivider) {
+                level.push(self.separator(addr));
+                groups.push(None);
+            }
+        }
+        // Separators aren't items of the set, and radio items only count their group
+        let mut total = 0;
+        let mut group_sizes = vec![0; group_count + 1];
+        for ((_, node), group) in level.iter().zip(&groups) {
+            if node.role() != Role::Splitter {
+                total += 1;
+                if let Some(g) = group {
+                    group_sizes[*g] += 1;
+                }
+            }
+        }
+        let mut pos = 0;
+        let mut group_pos = vec![0; group_count + 1];
+        for ((_, node), group) in level.iter_mut().zip(&groups) {
+            if node.role() == Role::Splitter {
+                continue;
+            }
+            pos += 1;
+            match group {
+                Some(g) => {
+                    group_pos[*g] += 1;
+                    node.set_position_in_set(group_pos[*g]);
+                    node.set_size_of_set(group_sizes[*g]);
+                }
+                None => {
+                    node.set_position_in_set(pos);
+                    node.set_size_of_set(total);
+                }
+            }
+        }
+        let ids = level.iter().map(|(id, _)| *id).collect();
+        out.extend(level);
+        ids
+    }
+
+    /// The separator after the item at address `addr`.
+    fn separator(&self, addr: usize) -> (NodeId, Node) {
+        // Items are aligned, so the address after an item's can't be another's
+        let id = child_node_id(self.owner, addr as u64 + 1);
+        (id, Node::new(Role::Splitter))
+    }
+}
+
+/// Where an item is: at `index` in the array starting at `first`, inside `submenus`.
+struct Place {
+    first: menu::MenuItem,
+    index: i32,
+    submenus: Vec<menu::MenuItem>,
+}
+
+/// Finds the item at address `addr` in the array starting at `first`, which is inside
+/// `outer`, following submenu pointers.
+fn find(
+    first: &menu::MenuItem,
+    addr: usize,
+    outer: &[menu::MenuItem],
+    depth: usize,
+) -> Option<Place> {
+    let mut submenus = outer.to_vec();
+    let mut i = 0;
+    loop {
+        let item = item_at(first, i);
+        if item.label().is_none() {
+            // The end of the array, or of a submenu in it
+            if submenus.len() == outer.len() {
+                return None;
+            }
+            submenus.pop();
+        } else if unsafe { item.as_ptr() } as usize == addr {
+            return Some(Place {
+                first: first.clone(),
+                index: i,
+                submenus,
+            });
+        } else if flags_of(&item).contains(MenuFlag::Submenu) {
+            submenus.push(item);
+        } else if let Some(sub) = pointed(&item).filter(|_| depth < MAX_DEPTH) {
+            submenus.push(item);
+            if let Some(place) = find(&sub, addr, &submenus, depth + 1) {
+                return Some(place);
+            }
+            submenus.pop();
+        }
+        i += 1;
+    }
+}
+
+/// Where the item at address `item` of `menu` is, if it's still there.
+fn locate(menu: &widget::Widget, item: usize) -> Option<Place> {
+    let p = menu.as_widget_ptr();
+    // SysMenuBar derives from MenuBar
+    let first = if let Some(m) = menu::MenuBar::from_dyn_widget_ptr(p) {
+        first_item(&m)
+    } else if let Some(m) = menu::MenuButton::from_dyn_widget_ptr(p) {
+        first_item(&m)
+    } else {
+        menu::Choice::from_dyn_widget_ptr(p).and_then(|m| first_item(&m))
+    };
+    find(&first?, item, &[], 0)
+}
+
+/// Whether the item at address `item` is still one of `menu`'s.
+pub(crate) fn contains(menu: &widget::Widget, item: usize) -> bool {
+    locate(menu, item).is_some()
+}
+
+/// Picks the item at address `item` of `menu` like a click on it would, through
+/// `Fl_Menu_::picked`: toggles checkbox and radio items, makes it the menu's value and
+/// runs the item's callback, or the menu's if the item has none, as `when` says.
+pub(crate) fn pick(menu: &widget::Widget, item: usize) {
+    let Some(place) = locate(menu, item) else {
+        return;
+    };
+    let p = menu.as_widget_ptr();
+    // SysMenuBar derives from MenuBar
+    if let Some(mut m) = menu::MenuBar::from_dyn_widget_ptr(p) {
+        picked(&mut m, &place);
+    } else if let Some(mut m) = menu::MenuButton::from_dyn_widget_ptr(p) {
+        picked(&mut m, &place);
+    } else if let Some(mut m) = menu::Choice::from_dyn_widget_ptr(p) {
+        picked(&mut m, &place);
+    }
+}
+
+/// Whether the item at address `item` of `menu` can be picked: it's active and shown,
+/// as are the submenus it's in, and isn't a submenu itself. The nodes of other items
+/// only lack the click action, which doesn't stop a client from requesting it.
+pub(crate) fn pickable(menu: &widget::Widget, item: usize) -> bool {
+    let Some(place) = locate(menu, item) else {
+        return false;
+    };
+    let usable = |it: &menu::MenuItem| it.active() && it.visible();
+    let item = item_at(&place.first, place.index);
+    !item.is_submenu() && usable(&item) && place.submenus.iter().all(usable)
+}
+
+fn picked<M: MenuExt>(menu: &mut M, place: &Place) {
+    let mut item = item_at(&place.first, place.index);
+    let flags = flags_of(&item);
+    let changed = if flags.contains(MenuFlag::Radio) {
+        let on = item.value();
+        if !on {
+            set_only(&place.first, place.index);
+        }
+        menu.redraw();
+        !on
+    } else if flags.contains(MenuFlag::Toggle) {
+        if item.value() {
+            item.clear();
+        } else {
+            item.set();
+        }
+        menu.redraw();
+        true
+    } else {
+        let addr = unsafe { item.as_ptr() };
+        menu.mvalue().is_none_or(|v| unsafe { v.as_ptr() } != addr)
+    };
+    // Clears the changed flag
+    menu.set_item(&item);
+    if changed {
+        menu.set_changed();
+    }
+    let when = menu.trigger();
+    if when.intersects(CallbackTrigger::Changed | CallbackTrigger::Release)
+        && (changed || when.contains(CallbackTrigger::NotChanged))
+    {
+        if head(&item).callback.is_null() {
+            menu.do_callback();
+        } else {
+            item.do_callback(&*menu);
+        }
+    }
+}
+
+/// Turns on the radio item at `index` of the array starting at `first` and off the
+/// others of its group, like `Fl_Menu_Item::setonly`.
+fn set_only(first: &menu::MenuItem, index: i32) {
+    let in_group = |i: i32| {
+        let it = item_at(first, i);
+        it.label().is_some() && flags_of(&it).contains(MenuFlag::Radio)
+    };
+    let divider = |i: i32| flags_of(&item_at(first, i)).contains(MenuFlag::MenuDivider);
+    let mut i = index;
+    while !divider(i) {
+        i += 1;
+        if !in_group(i) {
+            break;
+        }
+        item_at(first, i).clear();
+    }
+    let mut i = index - 1;
+    while i >= 0 && in_group(i) && !divider(i) {
+        item_at(first, i).clear();
+        i -= 1;
+    }
+    item_at(first, index).set();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ids::node_id;
+    use fltk::enums::Shortcut;
+    use std::collections::HashMap;
+
+    /// The nodes of `m`'s items by id, and the ids of the top-level ones.
+    fn walk<M: MenuExt>(m: &M) -> (HashMap<NodeId, Node>, Vec<NodeId>) {
+        let mut out = Vec::new();
+        let top = MenuWalker {
+            menu: m,
+            owner: node_id(m),
+            item_role: Role::MenuItem,
+            chosen: None,
+        }
+        .walk(&mut out, &mut Targets::default());
+        (out.into_iter().collect(), top)
+    }
+
+    fn id<M: MenuExt>(m: &M, path: &str) -> NodeId {
+        let item = m.find_item(path).unwrap();
+        child_node_id(node_id(m), unsafe { item.as_ptr() } as u64)
+    }
+
+    fn set_of(nodes: &HashMap<NodeId, Node>, id: NodeId) -> (Option<usize>, Option<usize>) {
+        let node = &nodes[&id];
+        (node.position_in_set(), node.size_of_set())
+    }
+
+    #[test]
+    fn radio_groups() {
+        let mut m = menu::MenuButton::default();
+        let radio = MenuFlag::Radio;
+        for (label, flags) in [
+            ("A", radio),
+            ("B", radio | MenuFlag::MenuDivider),
+            ("C", radio),
+            ("D", MenuFlag::Normal),
+            ("E", radio),
+            ("Hidden", MenuFlag::Invisible),
+            ("G", radio),
+            ("Hidden radio", radio | MenuFlag::Invisible),
+            ("H", radio),
+        ] {
+            m.add(label, Shortcut::None, flags, |_| ());
+        }
+        let (nodes, top) = walk(&m);
+        // A divider ends a group
+        assert_eq!(set_of(&nodes, id(&m, "A")), (Some(1), Some(2)));
+        assert_eq!(set_of(&nodes, id(&m, "B")), (Some(2), Some(2)));
+        assert_eq!(set_of(&nodes, id(&m, "C")), (Some(1), Some(1)));
+        // Other items count every item but the separators
+        assert_eq!(nodes[&id(&m, "D")].role(), Role::MenuItem);
+        assert_eq!(set_of(&nodes, id(&m, "D")), (Some(4), Some(7)));
+        // Hidden items count too: a hidden radio item is part of the group around it,
+        // any other hidden item ends it
+        assert_eq!(set_of(&nodes, id(&m, "E")), (Some(1), Some(1)));
+        assert_eq!(set_of(&nodes, id(&m, "G")), (Some(1), Some(2)));
+        assert_eq!(set_of(&nodes, id(&m, "H")), (Some(2), Some(2)));
+        assert_eq!(nodes[&id(&m, "H")].role(), Role::MenuItemRadio);
+        assert_eq!(nodes[&id(&m, "H")].toggled(), Some(Toggled::False));
+        // The separator is a node of its own, outside the set
+        assert_eq!(top.len(), 8);
+        let separator = &nodes[&top[2]];
+        assert_eq!(separator.role(), Role::Splitter);
+        assert_eq!(separator.position_in_set(), None);
+    }
+
+    #[test]
+    fn nesting() {
+        let mut m = menu::MenuBar::default();
+        let none = MenuFlag::Normal;
+        for (path, flags) in [
+            ("File/Open", none),
+            ("File/Recent/Projects/a.txt", none),
+            ("File/Recent/Projects/b.txt", none),
+            ("File/Quit", MenuFlag::Inactive),
+            ("Edit/Undo", MenuFlag::MenuDivider),
+            ("Edit/Gone", MenuFlag::Invisible),
+            ("Edit/Copy", none),
+            ("Edit/Cut", MenuFlag::Invisible | MenuFlag::MenuDivider),
+            ("Edit/Paste", none),
+            ("Hidden/Inner", none),
+            ("Help/About", none),
+        ] {
+            m.add(path, Shortcut::None, flags, |_| ());
+        }
+        m.find_item("Hidden").unwrap().hide();
+        // A submenu pointer, to the items of another menu
+        let mut other = menu::MenuButton::default();
+        other.add_choice("Zoom in|Zoom out");
+        let link = m.add_choice("View");
+        m.set_mode(link, MenuFlag::SubmenuPointer);
+        let pointed = other.menu().unwrap();
+        unsafe {
+            (*(m.at(link).unwrap().as_ptr() as *mut MenuItemRepr)).user_data =
+                pointed.as_ptr() as _;
+        }
+        let (nodes, top) = walk(&m);
+        let ids = |paths: &[&str]| paths.iter().map(|p| id(&m, p)).collect::<Vec<_>>();
+        let children = |path: &str| nodes[&id(&m, path)].children().to_vec();
+
+        // Hidden submenus are left out with their items, and the items after them are
+        // still at the right level
+        assert_eq!(top, ids(&["File", "Edit", "Help", "View"]));
+        assert!(!nodes.contains_key(&id(&m, "Hidden/Inner")));
+
+        // Any depth
+        assert_eq!(
+            children("File"),
+            ids(&["File/Open", "File/Recent", "File/Quit"])
+        );
+        assert_eq!(children("File/Recent"), ids(&["File/Recent/Projects"]));
+        assert_eq!(
+            children("File/Recent/Projects"),
+            ids(&["File/Recent/Projects/a.txt", "File/Recent/Projects/b.txt"])
+        );
+        assert_eq!(nodes[&id(&m, "File/Recent/Projects")].role(), Role::Menu);
+        let a = &nodes[&id(&m, "File/Recent/Projects/a.txt")];
+        assert_eq!(a.role(), Role::MenuItem);
+        assert!(a.supports_action(Action::Click));
+
+        // Inactive items are disabled and can't be clicked
+        let quit = &nodes[&id(&m, "File/Quit")];
+        assert!(quit.is_disabled());
+        assert!(!quit.supports_action(Action::Click));
+
+        // Dividers are separators, and those of hidden items stay
+        let after = |path: &str| {
+            let item = m.find_item(path).unwrap();
+            child_node_id(node_id(&m), unsafe { item.as_ptr() } as u64 + 1)
+        };
+        let mut edit = ids(&["Edit/Undo", "Edit/Copy", "Edit/Paste"]);
+        edit.insert(1, after("Edit/Undo"));
+        edit.insert(3, after("Edit/Cut"));
+        assert_eq!(children("Edit"), edit);
+        assert_eq!(nodes[&edit[1]].role(), Role::Splitter);
+        assert_eq!(set_of(&nodes, id(&m, "Edit/Copy")), (Some(2), Some(3)));
+
+        // Submenu pointers are followed to the items they point to
+        let view = &nodes[&id(&m, "View")];
+        assert_eq!(view.role(), Role::Menu);
+        let zoom: Vec<NodeId> = (0..2)
+            .map(|i| {
+                let item = other.at(i).unwrap();
+                child_node_id(node_id(&m), unsafe { item.as_ptr() } as u64)
+            })
+            .collect();
+        assert_eq!(view.children(), zoom);
+        assert_eq!(nodes[&zoom[1]].label(), Some("Zoom out"));
+    }
+}
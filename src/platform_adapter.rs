@@ -76,6 +76,16 @@ impl Adapter {
         }
     }
 
+    /// Windows and macOS track the focused window themselves.
+    pub fn update_window_focus_state(&mut self, _is_focused: bool) {
+        #[cfg(not(any(target_os = "windows", target_os = "macos")))]
+        {
+            if let Some(adapter) = &mut self.adapter {
+                adapter.update_window_focus_state(_is_focused);
+            }
+        }
+    }
+
     pub fn update_if_active(&mut self, updater: impl FnOnce() -> TreeUpdate) {
         #[cfg(not(any(target_os = "windows", target_os = "macos")))]
         {
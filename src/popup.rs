@@ -0,0 +1,638 @@
+use fltk::{
+    app,
+    enums::{Event, Key},
+    group,
+    menu::{self, MenuFlag},
+    prelude::*,
+    utils, widget, window,
+};
+use std::cell::RefCell;
+
+use crate::label;
+
+thread_local! {
+    static TRACKER: RefCell<Tracker> = RefCell::new(Tracker::default());
+}
+
+/// fltk runs popup menus in a loop of their own, in windows that aren't part of the
+/// application, and keeps which item is highlighted and which submenus are open to
+/// itself, in a class private to `Fl_Menu.cxx`. This follows the menus from the events
+/// they get instead, replaying fltk's keyboard navigation.
+///
+/// That has limits:
+/// - The mouse highlights items by position, which isn't replayed: moving it over the
+///   menus forgets the highlighted item until the next key, and submenus opened by
+///   hovering aren't known to be open.
+/// - What's replayed is the menu as of when it opened; items changed while it's open,
+///   from a timeout say, are only seen the next time.
+/// - A popup that opens without an event we saw, like one an application opens from a
+///   timeout, isn't followed, unless it's one we opened through `expect`.
+#[derive(Default)]
+struct Tracker {
+    /// The menu widget that the event being handled may open the popup of
+    candidate: Option<widget::Widget>,
+    open: Option<Popup>,
+}
+
+/// An item of a menu's item array, as of when its popup opened.
+struct Entry {
+    flags: MenuFlag,
+    addr: usize,
+    label: Option<String>,
+    active: bool,
+    visible: bool,
+}
+
+/// An open menu window, one per level of submenus.
+struct Level {
+    /// The submenu item this level belongs to, `None` for the top one
+    title: Option<usize>,
+    /// The visible items, as indices into the entries
+    items: Vec<usize>,
+    /// Position of the item that stays highlighted when the focus moves to another level
+    selected: Option<usize>,
+}
+
+struct Popup {
+    menu: widget::Widget,
+    entries: Vec<Entry>,
+    menubar: bool,
+    levels: Vec<Level>,
+    /// Level of the highlighted item, fltk's `menu_number`
+    menu_number: usize,
+    /// Position of the highlighted item within its level, fltk's `item_number`
+    item_number: Option<usize>,
+}
+
+/// What's shown of a menu's popup.
+pub(crate) struct Shown {
+    /// Addresses of the submenu items whose menus are open
+    pub submenus: Vec<usize>,
+    /// Address of the highlighted item
+    pub highlighted: Option<usize>,
+}
+
+fn entries<M: MenuExt>(m: &M) -> Vec<Entry> {
+    (0..m.size())
+        .filter_map(|i| {
+            let item = m.at(i)?;
+            Some(Entry {
+                flags: m.mode(i),
+                addr: unsafe { item.as_ptr() } as usize,
+                label: item.label(),
+                active: item.active(),
+                visible: item.visible(),
+            })
+        })
+        .collect()
+}
+
+/// The entries of `w`, whether it's a menu bar and the address of its value.
+fn snapshot(w: &widget::Widget) -> Option<(Vec<Entry>, bool, Option<usize>)> {
+    let p = w.as_widget_ptr();
+    let value = |v: Option<menu::MenuItem>| v.map(|it| unsafe { it.as_ptr() } as usize);
+    // SysMenuBar derives from MenuBar
+    if let Some(m) = menu::MenuBar::from_dyn_widget_ptr(p) {
+        return Some((entries(&m), true, value(m.mvalue())));
+    }
+    if let Some(m) = menu::MenuButton::from_dyn_widget_ptr(p) {
+        return Some((entries(&m), false, value(m.mvalue())));
+    }
+    let m = menu::Choice::from_dyn_widget_ptr(p)?;
+    Some((entries(&m), false, value(m.mvalue())))
+}
+
+fn is_menu(w: &widget::Widget) -> bool {
+    let p = w.as_widget_ptr();
+    utils::is_ptr_of::<menu::MenuBar>(p)
+        || utils::is_ptr_of::<menu::MenuButton>(p)
+        || utils::is_ptr_of::<menu::Choice>(p)
+}
+
+impl Popup {
+    fn start(menu: widget::Widget, initial: Option<Initial>) -> Option<Self> {
+        let (entries, menubar, value) = snapshot(&menu)?;
+        let mut p = Popup {
+            menu,
+            entries,
+            menubar,
+            levels: Vec::new(),
+            menu_number: 0,
+            item_number: None,
+        };
+        p.levels.push(p.level(None));
+        // A choice opens on its value, a menu bar on the title of the key pressed
+        let target = match initial {
+            Some(Initial::Value) => value.and_then(|a| p.entries.iter().position(|e| e.addr == a)),
+            Some(Initial::AccessKey(k)) => p.levels[0].items.iter().copied().find(|&i| {
+                let e = &p.entries[i];
+                e.label
+                    .as_deref()
+                    .and_then(|l| label::parse(l, false).access_key)
+                    .is_some_and(|a| a.to_lowercase().to_string() == k.to_lowercase())
+            }),
+            None => None,
+        };
+        if let Some(path) = target.and_then(|t| p.path_to(None, t)) {
+            for (depth, pos) in path.iter().enumerate() {
+                p.levels[depth].selected = Some(*pos);
+                if depth + 1 < path.len() {
+                    let title = p.levels[depth].items[*pos];
+                    p.levels.push(p.level(Some(title)));
+                }
+            }
+            p.menu_number = path.len() - 1;
+            p.item_number = path.last().copied();
+            p.settle();
+        }
+        Some(p)
+    }
+
+    /// The level of the items after `title`, or of the top items.
+    fn level(&self, title: Option<usize>) -> Level {
+        let mut items = Vec::new();
+        let mut i = title.map_or(0, |t| t + 1);
+        while let Some(e) = self.entries.get(i) {
+            if e.label.is_none() {
+                break;
+            }
+            if e.visible {
+                items.push(i);
+            }
+            i = self.skip(i);
+        }
+        Level {
+            title,
+            items,
+            selected: None,
+        }
+    }
+
+    /// The index after entry `i` and the items of its submenu, if it has one.
+    fn skip(&self, i: usize) -> usize {
+        if !self.entries[i].flags.contains(MenuFlag::Submenu) {
+            return i + 1;
+        }
+        let mut depth = 1;
+        let mut j = i + 1;
+        while depth > 0 && j < self.entries.len() {
+            let e = &self.entries[j];
+            if e.label.is_none() {
+                depth -= 1;
+            } else if e.flags.contains(MenuFlag::Submenu) {
+                depth += 1;
+            }
+            j += 1;
+        }
+        j
+    }
+
+    /// Positions of the items leading from the level of `title` to entry `target`.
+    fn path_to(&self, title: Option<usize>, target: usize) -> Option<Vec<usize>> {
+        let level = self.level(title);
+        for (pos, &i) in level.items.iter().enumerate() {
+            if i == target {
+                return Some(vec![pos]);
+            }
+            if self.entries[i].flags.contains(MenuFlag::Submenu) {
+                if let Some(mut rest) = self.path_to(Some(i), target) {
+                    rest.insert(0, pos);
+                    return Some(rest);
+                }
+            }
+        }
+        None
+    }
+
+    fn current(&self) -> Option<usize> {
+        let level = self.levels.get(self.menu_number)?;
+        level.items.get(self.item_number?).copied()
+    }
+
+    fn set_item(&mut self, menu: usize, item: Option<usize>) {
+        self.menu_number = menu;
+        self.item_number = item;
+    }
+
+    /// The next active item of level `menu`, wrapping around unless `wrap` is false.
+    fn forward(&mut self, menu: usize, wrap: bool) -> bool {
+        self.step(menu, wrap, true)
+    }
+
+    fn backward(&mut self, menu: usize, wrap: bool) -> bool {
+        self.step(menu, wrap, false)
+    }
+
+    fn step(&mut self, menu: usize, wrap: bool, ahead: bool) -> bool {
+        let Some(level) = self.levels.get(menu) else {
+            return false;
+        };
+        let from = if menu == self.menu_number {
+            self.item_number
+        } else {
+            level.selected
+        };
+        let n = level.items.len();
+        let active = |pos: &usize| self.entries[level.items[*pos]].active;
+        let found = if ahead {
+            let after = from.map_or(0, |f| f + 1);
+            (after..n)
+                .find(active)
+                .or_else(|| (0..n).find(active).filter(|_| wrap))
+        } else {
+            let before = from.unwrap_or(n);
+            (0..before)
+                .rev()
+                .find(active)
+                .or_else(|| (0..n).rev().find(active).filter(|_| wrap))
+        };
+        match found {
+            Some(pos) => {
+                self.set_item(menu, Some(pos));
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Replays `Fl_Menu`'s handling of a key press.
+    fn key(&mut self, key: Key, shift: bool) {
+        let before = self.current();
+        let last = self.levels.len() - 1;
+        let (menubar, mn) = (self.menubar, self.menu_number);
+        match key {
+            Key::BackSpace => {
+                self.backward(mn, true);
+            }
+            Key::Tab if shift => {
+                self.backward(mn, true);
+            }
+            Key::Up => {
+                // Going up from the first item of a menu bar's menu goes to its title
+                if !(menubar && mn == 0) && !self.backward(mn, false) && menubar && mn == 1 {
+                    let selected = self.levels[0].selected;
+                    self.set_item(0, selected);
+                }
+            }
+            Key::Tab if menubar && mn == 0 => self.right(),
+            Key::Tab | Key::Down => {
+                let wrap = key != Key::Down;
+                if mn > 0 || !menubar {
+                    self.forward(mn, wrap);
+                } else if mn < last {
+                    self.forward(mn + 1, wrap);
+                }
+            }
+            Key::Right => self.right(),
+            Key::Left => {
+                if menubar && mn <= 1 {
+                    self.backward(0, true);
+                } else if mn > 0 {
+                    let selected = self.levels[mn - 1].selected;
+                    self.set_item(mn - 1, selected);
+                }
+            }
+            _ => return,
+        }
+        if self.current() != before {
+            self.settle();
+        }
+    }
+
+    fn right(&mut self) {
+        let last = self.levels.len() - 1;
+        if self.menubar && (self.menu_number == 0 || self.menu_number == last) {
+            self.forward(0, true);
+        } else if self.menu_number < last {
+            self.forward(self.menu_number + 1, true);
+        }
+    }
+
+    /// Opens and closes menus to match the highlighted item, like the loop of
+    /// `Fl_Menu_Item::pulldown` does once it changes.
+    fn settle(&mut self) {
+        let Some(current) = self.current() else {
+            if let Some(level) = self.levels.last_mut() {
+                level.selected = None;
+            }
+            return;
+        };
+        let mn = self.menu_number;
+        if !self.entries[current].active {
+            self.levels[mn].selected = None;
+            return;
+        }
+        self.levels[mn].selected = self.item_number;
+        if self.entries[current].flags.contains(MenuFlag::Submenu) {
+            let up = self
+                .levels
+                .get(mn + 1)
+                .is_some_and(|l| l.title == Some(current));
+            if up {
+                self.levels.truncate(mn + 2);
+                self.levels[mn + 1].selected = None;
+            } else {
+                self.levels.truncate(mn + 1);
+                let level = self.level(Some(current));
+                self.levels.push(level);
+            }
+        } else {
+            self.levels.truncate(mn + 1);
+        }
+    }
+
+    /// The pointer moved, which highlights whatever item it's over.
+    fn pointer_moved(&mut self) {
+        self.item_number = None;
+    }
+
+    fn shown(&self) -> Shown {
+        Shown {
+            submenus: self
+                .levels
+                .iter()
+                .filter_map(|l| l.title)
+                .map(|t| self.entries[t].addr)
+                .collect(),
+            highlighted: self.current().map(|c| self.entries[c].addr),
+        }
+    }
+}
+
+/// Where the highlight starts when a popup opens.
+enum Initial {
+    /// On the menu's value
+    Value,
+    /// On the top item with this access key
+    AccessKey(String),
+}
+
+/// The window fltk has grabbed the events for, if it's one of its menu windows.
+fn menu_window() -> Option<window::Window> {
+    let grab = app::grab()?;
+    let p = grab.as_widget_ptr();
+    if !utils::is_ptr_of::<window::MenuWindow>(p) {
+        return None;
+    }
+    window::Window::from_dyn_widget_ptr(p)
+}
+
+/// Whether `w` is one of fltk's menu or tooltip windows.
+pub(crate) fn is_menu_window(w: &window::Window) -> bool {
+    utils::is_ptr_of::<window::MenuWindow>(w.as_widget_ptr())
+}
+
+/// The shown menu widgets in `g`, in drawing order.
+fn menus_in(g: &group::Group, out: &mut Vec<widget::Widget>) {
+    for c in (0..g.children()).filter_map(|i| g.child(i)) {
+        if !c.visible() {
+            continue;
+        }
+        if is_menu(&c) {
+            out.push(c);
+        } else if let Some(g) = c.as_group() {
+            menus_in(&g, out);
+        }
+    }
+}
+
+/// The shown menu widgets of `win`, in drawing order.
+fn menus(win: Option<&window::Window>) -> Vec<widget::Widget> {
+    let mut out = Vec::new();
+    if let Some(g) = win.filter(|w| w.shown()).and_then(|w| w.as_group()) {
+        menus_in(&g, &mut out);
+    }
+    out
+}
+
+/// Whether the screen position `x`, `y` is on `w`.
+fn contains(w: &widget::Widget, x: i32, y: i32) -> bool {
+    let (mut wx, mut wy) = (w.x(), w.y());
+    let mut win = w.window();
+    while let Some(parent) = win {
+        wx += parent.x();
+        wy += parent.y();
+        win = parent.window();
+    }
+    x >= wx && x < wx + w.w() && y >= wy && y < wy + w.h()
+}
+
+/// Notes which menu `ev` may open a popup of, before it's handled. Only the menus of
+/// `win`, the window the event goes to, can get it.
+pub(crate) fn before(ev: Event, win: app::WindowPtr) {
+    TRACKER.with(|t| {
+        let mut t = t.borrow_mut();
+        if t.open.is_some() {
+            return;
+        }
+        let win = if win.is_null() {
+            None
+        } else {
+            window::Window::from_dyn_widget_ptr(win as _)
+        };
+        t.candidate = match ev {
+            Event::Push => {
+                let (x, y) = (app::event_x_root(), app::event_y_root());
+                menus(win.as_ref())
+                    .into_iter()
+                    .rev()
+                    .find(|m| contains(m, x, y))
+            }
+            Event::KeyDown => app::focus()
+                .map(|f| f.as_base_widget())
+                .filter(is_menu)
+                .or_else(|| {
+                    // Menu bars take the Alt shortcuts of their titles
+                    if !app::is_event_alt() {
+                        return None;
+                    }
+                    menus(win.as_ref())
+                        .into_iter()
+                        .find(|m| utils::is_ptr_of::<menu::MenuBar>(m.as_widget_ptr()))
+                }),
+            _ => return,
+        };
+    });
+}
+
+/// Follows what handling `ev` did to the popup.
+pub(crate) fn after(ev: Event) {
+    sync();
+    TRACKER.with(|t| {
+        let mut t = t.borrow_mut();
+        let Some(p) = t.open.as_mut() else {
+            return;
+        };
+        match ev {
+            Event::KeyDown => p.key(app::event_key(), app::is_event_shift()),
+            Event::Move | Event::Drag | Event::Push => p.pointer_moved(),
+            _ => (),
+        }
+    });
+}
+
+/// Starts following a popup that fltk opened, and stops once it's closed.
+pub(crate) fn sync() {
+    TRACKER.with(|t| {
+        let mut t = t.borrow_mut();
+        match (menu_window().is_some(), t.open.is_some()) {
+            (true, false) => {
+                let Some(menu) = t.candidate.take().filter(|m| !m.was_deleted()) else {
+                    return;
+                };
+                let initial = if utils::is_ptr_of::<menu::Choice>(menu.as_widget_ptr()) {
+                    Some(Initial::Value)
+                } else if utils::is_ptr_of::<menu::MenuBar>(menu.as_widget_ptr())
+                    && app::is_event_alt()
+                {
+                    Some(Initial::AccessKey(app::event_text()))
+                } else {
+                    None
+                };
+                t.open = Popup::start(menu, initial);
+            }
+            (false, true) => {
+                t.open = None;
+                t.candidate = None;
+            }
+            _ => (),
+        }
+    });
+}
+
+/// Tells the tracker that `menu` is about to open its popup.
+pub(crate) fn expect<W: WidgetExt>(menu: &W) {
+    TRACKER.with(|t| t.borrow_mut().candidate = Some(menu.as_base_widget()));
+}
+
+/// What's shown of `menu`'s popup, if it's open.
+pub(crate) fn shown(menu: &widget::Widget) -> Option<Shown> {
+    TRACKER.with(|t| {
+        let t = t.borrow();
+        let p = t.open.as_ref()?;
+        (p.menu.as_widget_ptr() == menu.as_widget_ptr()).then(|| p.shown())
+    })
+}
+
+/// The menu whose popup is open and the address of its highlighted item. Choices keep
+/// the focus and point at the item as their active descendant instead.
+pub(crate) fn highlighted() -> Option<(widget::Widget, usize)> {
+    TRACKER.with(|t| {
+        let t = t.borrow();
+        let p = t
+            .open
+            .as_ref()
+            .filter(|p| !utils::is_ptr_of::<menu::Choice>(p.menu.as_widget_ptr()))?;
+        let addr = p.shown().highlighted?;
+        Some((p.menu.clone(), addr))
+    })
+}
+
+/// Whether a popup is open.
+pub(crate) fn is_open() -> bool {
+    menu_window().is_some()
+}
+
+/// Whether [`close`] can close the open popup. fltk's popups only close on their own on
+/// Escape or on a click outside of them, and fltk has no way to fake a key, nor to move
+/// where it thinks the pointer is. So the click has to be where the pointer last was,
+/// which only closes the popup while that's outside of the menus and of the menu bar they
+/// drop from, where a click would pick a title instead.
+pub(crate) fn can_close() -> bool {
+    if menu_window().is_none() {
+        return false;
+    }
+    let (x, y) = (app::event_x_root(), app::event_y_root());
+    let over_menus = app::windows()
+        .unwrap_or_default()
+        .iter()
+        .any(|w| is_menu_window(w) && w.shown() && contains(&w.as_base_widget(), x, y));
+    let over_bar = TRACKER.with(|t| {
+        t.borrow()
+            .open
+            .as_ref()
+            .is_some_and(|p| p.menubar && contains(&p.menu, x, y))
+    });
+    !over_menus && !over_bar
+}
+
+/// Closes the open popup the way a click outside of it does, and returns whether it did,
+/// see [`can_close`].
+pub(crate) fn close() -> bool {
+    let Some(win) = menu_window().filter(|_| can_close()) else {
+        return false;
+    };
+    app::handle(Event::Push, &win).unwrap_or(false)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use fltk::enums::Shortcut;
+
+    fn addr<M: MenuExt>(m: &M, path: &str) -> Option<usize> {
+        m.find_item(path).map(|it| unsafe { it.as_ptr() } as usize)
+    }
+
+    #[test]
+    fn keyboard_navigation() {
+        let mut b = menu::MenuButton::default();
+        b.add("Open", Shortcut::None, MenuFlag::Normal, |_| ());
+        b.add("Gone", Shortcut::None, MenuFlag::Invisible, |_| ());
+        b.add("Off", Shortcut::None, MenuFlag::Inactive, |_| ());
+        b.add("Recent/a.txt", Shortcut::None, MenuFlag::Normal, |_| ());
+        b.add("Recent/b.txt", Shortcut::None, MenuFlag::Normal, |_| ());
+        let mut p = Popup::start(b.as_base_widget(), None).unwrap();
+        assert_eq!(p.shown().highlighted, None);
+        p.key(Key::Down, false);
+        assert_eq!(p.shown().highlighted, addr(&b, "Open"));
+        // Hidden and inactive items are passed over, and a submenu opens once highlighted
+        p.key(Key::Down, false);
+        assert_eq!(p.shown().highlighted, addr(&b, "Recent"));
+        assert_eq!(
+            p.shown().submenus,
+            addr(&b, "Recent").into_iter().collect::<Vec<_>>()
+        );
+        p.key(Key::Right, false);
+        assert_eq!(p.shown().highlighted, addr(&b, "Recent/a.txt"));
+        p.key(Key::Down, false);
+        assert_eq!(p.shown().highlighted, addr(&b, "Recent/b.txt"));
+        // Down doesn't wrap around, Tab does
+        p.key(Key::Down, false);
+        assert_eq!(p.shown().highlighted, addr(&b, "Recent/b.txt"));
+        p.key(Key::Tab, false);
+        assert_eq!(p.shown().highlighted, addr(&b, "Recent/a.txt"));
+        // Left goes back to the submenu's title, leaving it open
+        p.key(Key::Left, false);
+        assert_eq!(p.shown().highlighted, addr(&b, "Recent"));
+        assert_eq!(p.shown().submenus.len(), 1);
+        // Where the pointer is isn't known, so what it highlights isn't either
+        p.pointer_moved();
+        assert_eq!(p.shown().highlighted, None);
+        p.key(Key::Up, false);
+        assert_eq!(p.shown().highlighted, addr(&b, "Recent"));
+    }
+
+    #[test]
+    fn choices_open_on_their_value() {
+        let mut c = menu::Choice::default();
+        c.add_choice("One|Two|Three");
+        c.set_value(1);
+        let p = Popup::start(c.as_base_widget(), Some(Initial::Value)).unwrap();
+        assert_eq!(p.shown().highlighted, addr(&c, "Two"));
+    }
+
+    #[test]
+    fn menu_bars_open_on_the_title_of_the_key() {
+        let mut bar = menu::MenuBar::default();
+        bar.add("&File/&Open", Shortcut::None, MenuFlag::Normal, |_| ());
+        bar.add("&Edit/&Copy", Shortcut::None, MenuFlag::Normal, |_| ());
+        let p = Popup::start(bar.as_base_widget(), Some(Initial::AccessKey("e".into()))).unwrap();
+        let shown = p.shown();
+        assert_eq!(shown.highlighted, addr(&bar, "&Edit"));
+        assert_eq!(
+            shown.submenus,
+            addr(&bar, "&Edit").into_iter().collect::<Vec<_>>()
+        );
+    }
+}
@@ -0,0 +1,165 @@
+use accesskit::{Node, Role};
+use fltk::{prelude::*, widget};
+use std::cell::RefCell;
+use std::collections::HashMap;
+
+thread_local! {
+    static PROPS: RefCell<Table> = RefCell::new(Table::default());
+}
+
+/// Accessible properties set by the application, overriding what the built-in impls
+/// derive from the widget.
+#[derive(Clone, Default, PartialEq)]
+struct Props {
+    name: Option<String>,
+    description: Option<String>,
+    role: Option<Role>,
+    level: Option<usize>,
+    hidden: bool,
+}
+
+/// Keyed by widget address, with a widget handle so that a later widget allocated at the
+/// same address doesn't inherit the entry.
+#[derive(Default)]
+struct Table {
+    widgets: HashMap<u64, (widget::Widget, Props)>,
+}
+
+impl Table {
+    fn edit(&mut self, w: &widget::Widget, f: impl FnOnce(&mut Props)) {
+        let entry = self
+            .widgets
+            .entry(w.as_widget_ptr() as usize as u64)
+            .or_insert_with(|| (w.clone(), Props::default()));
+        // Left by a deleted widget that `w` took the address of
+        if entry.0.was_deleted() {
+            *entry = (w.clone(), Props::default());
+        }
+        f(&mut entry.1);
+    }
+
+    fn prune(&mut self) {
+        self.widgets.retain(|_, (h, _)| !h.was_deleted());
+    }
+
+    fn get(&self, w: &widget::Widget) -> Option<&Props> {
+        self.widgets
+            .get(&(w.as_widget_ptr() as usize as u64))
+            .filter(|(h, _)| !h.was_deleted())
+            .map(|(_, p)| p)
+    }
+}
+
+/// Overrides the accessible properties of `w`'s node:
+/// ```rust,no_run
+/// use fltk::{prelude::*, *};
+/// let title = frame::Frame::default().with_label("Settings");
+/// fltk_accesskit::props(&title)
+///     .role(accesskit::Role::Heading)
+///     .level(1);
+/// ```
+/// The properties stay with the widget until it's deleted.
+pub fn props<W: WidgetExt>(w: &W) -> PropsMut {
+    PropsMut {
+        w: w.as_base_widget(),
+    }
+}
+
+/// Sets the accessible properties of a widget, see [`props`].
+pub struct PropsMut {
+    w: widget::Widget,
+}
+
+impl PropsMut {
+    fn edit(self, f: impl FnOnce(&mut Props)) -> Self {
+        PROPS.with(|t| t.borrow_mut().edit(&self.w, f));
+        self
+    }
+    /// The name announced for the widget, instead of its label.
+    pub fn name(self, name: &str) -> Self {
+        self.edit(|p| p.name = Some(name.to_string()))
+    }
+    /// A description of the widget, instead of its tooltip.
+    pub fn description(self, description: &str) -> Self {
+        self.edit(|p| p.description = Some(description.to_string()))
+    }
+    pub fn role(self, role: Role) -> Self {
+        self.edit(|p| p.role = Some(role))
+    }
+    /// The level of a heading, starting at 1.
+    pub fn level(self, level: usize) -> Self {
+        self.edit(|p| p.level = Some(level))
+    }
+    /// Hide the widget from assistive technologies while it's on screen, like a
+    /// decorative image.
+    pub fn hidden(self, hidden: bool) -> Self {
+        self.edit(|p| p.hidden = hidden)
+    }
+    /// Go back to what the built-in impl derives from the widget.
+    pub fn reset(self) -> Self {
+        self.edit(|p| *p = Props::default())
+    }
+}
+
+/// Whether any property of `w` is overridden.
+pub(crate) fn any(w: &widget::Widget) -> bool {
+    PROPS.with(|t| t.borrow().get(w).is_some_and(|p| *p != Props::default()))
+}
+
+/// Forgets the properties of deleted widgets. Done along with the sweep of their ids
+/// rather than on every edit, which would go through all of them each time.
+pub(crate) fn prune() {
+    PROPS.with(|t| t.borrow_mut().prune());
+}
+
+/// Applies the properties set for `w` to its node.
+pub(crate) fn apply(w: &widget::Widget, node: &mut Node) {
+    PROPS.with(|t| {
+        let t = t.borrow();
+        let Some(p) = t.get(w) else {
+            return;
+        };
+        if let Some(name) = &p.name {
+            node.set_label(name.as_str());
+        }
+        if let Some(description) = &p.description {
+            node.set_description(description.as_str());
+        }
+        if let Some(role) = p.role {
+            node.set_role(role);
+        }
+        if let Some(level) = p.level {
+            node.set_level(level);
+        }
+        if p.hidden {
+            node.set_hidden();
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use fltk::frame;
+
+    #[test]
+    fn deleted_widgets_lose_their_props() {
+        let mut t = Table::default();
+        let old = frame::Frame::default();
+        let old_w = old.as_base_widget();
+        t.edit(&old_w, |p| p.name = Some("Old".into()));
+        let stale = t.widgets.values().next().cloned().unwrap();
+        frame::Frame::delete(old);
+        assert!(t.get(&old_w).is_none());
+        // Entries of deleted widgets stay until pruned
+        assert_eq!(t.widgets.len(), 1);
+        t.prune();
+        assert!(t.widgets.is_empty());
+        // A widget at the address of a deleted one starts over
+        let new = frame::Frame::default().as_base_widget();
+        t.widgets.insert(new.as_widget_ptr() as usize as u64, stale);
+        t.edit(&new, |p| p.level = Some(1));
+        let props = t.get(&new).unwrap();
+        assert_eq!((props.name.as_deref(), props.level), (None, Some(1)));
+    }
+}
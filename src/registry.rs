@@ -0,0 +1,68 @@
+use accesskit::{Node, NodeId};
+use fltk::{prelude::*, widget};
+use std::any::TypeId;
+use std::collections::HashMap;
+use std::rc::Rc;
+
+use crate::Accessible;
+
+type Factory = Box<dyn Fn(&widget::Widget) -> Box<dyn Accessible>>;
+
+/// Custom `Accessible` impls registered at runtime, consulted before the built-in ones.
+///
+/// Entries are keyed by widget address and hold a widget handle so that a later widget
+/// allocated at the same address doesn't inherit them.
+#[derive(Default)]
+pub(crate) struct Registry {
+    widgets: HashMap<u64, (widget::Widget, Rc<dyn Accessible>)>,
+    tags: HashMap<u64, (widget::Widget, TypeId)>,
+    types: HashMap<TypeId, Factory>,
+}
+
+impl Registry {
+    pub(crate) fn register_widget<W: WidgetExt>(&mut self, w: &W, acc: Box<dyn Accessible>) {
+        self.prune();
+        self.widgets
+            .insert(key(w), (w.as_base_widget(), Rc::from(acc)));
+    }
+
+    pub(crate) fn register_type<T: 'static>(
+        &mut self,
+        factory: impl Fn(&widget::Widget) -> Box<dyn Accessible> + 'static,
+    ) {
+        self.types.insert(TypeId::of::<T>(), Box::new(factory));
+    }
+
+    pub(crate) fn tag_widget<T: 'static, W: WidgetExt>(&mut self, w: &W) {
+        self.prune();
+        self.tags
+            .insert(key(w), (w.as_base_widget(), TypeId::of::<T>()));
+    }
+
+    /// The custom impl for this widget instance, or for the type it was tagged with.
+    pub(crate) fn accessible(&self, w: &widget::Widget) -> Option<Rc<dyn Accessible>> {
+        let k = key(w);
+        if let Some((_, acc)) = self.widgets.get(&k).filter(|(h, _)| !h.was_deleted()) {
+            return Some(acc.clone());
+        }
+        let (_, ty) = self.tags.get(&k).filter(|(h, _)| !h.was_deleted())?;
+        self.types.get(ty).map(|f| Rc::from(f(w)))
+    }
+
+    pub(crate) fn make_node(
+        &self,
+        w: &widget::Widget,
+        children: &[NodeId],
+    ) -> Option<(NodeId, Node)> {
+        self.accessible(w).map(|a| a.make_node(children))
+    }
+
+    fn prune(&mut self) {
+        self.widgets.retain(|_, (h, _)| !h.was_deleted());
+        self.tags.retain(|_, (h, _)| !h.was_deleted());
+    }
+}
+
+fn key<W: WidgetExt>(w: &W) -> u64 {
+    w.as_widget_ptr() as usize as u64
+}
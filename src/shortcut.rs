@@ -0,0 +1,148 @@
+use accesskit::Node;
+use fltk::enums::*;
+
+/// `bits` of an fltk [`Shortcut`] as people write them, like "Ctrl+Shift+S".
+pub(crate) fn text(bits: i32) -> Option<String> {
+    let key = bits & 0xffff;
+    if key == 0 {
+        return None;
+    }
+    let mut parts = Vec::new();
+    let has = |s: Shortcut| bits & s.bits() != 0;
+    if has(Shortcut::Ctrl) {
+        parts.push("Ctrl");
+    }
+    if has(Shortcut::Alt) {
+        parts.push(if cfg!(target_os = "macos") {
+            "Option"
+        } else {
+            "Alt"
+        });
+    }
+    if has(Shortcut::Shift) {
+        parts.push("Shift");
+    }
+    if has(Shortcut::Meta) {
+        parts.push(if cfg!(target_os = "macos") {
+            "Cmd"
+        } else {
+            "Meta"
+        });
+    }
+    let name = key_name(Key::from_i32(key))?;
+    let mut text = parts.join("+");
+    if !text.is_empty() {
+        text.push('+');
+    }
+    text.push_str(&name);
+    Some(text)
+}
+
+fn key_name(key: Key) -> Option<String> {
+    let bits = key.bits();
+    if (Key::F1.bits()..Key::FLast.bits()).contains(&bits) {
+        return Some(format!("F{}", bits - Key::F1.bits() + 1));
+    }
+    if bits > Key::KP.bits() && bits <= Key::KPLast.bits() {
+        if key == Key::KPEnter {
+            return Some("Num Enter".into());
+        }
+        let c = char::from_u32((bits - Key::KP.bits()) as u32)?;
+        return Some(format!("Num {c}"));
+    }
+    let name = match key {
+        Key::BackSpace => "Backspace",
+        Key::Tab => "Tab",
+        Key::Enter => "Enter",
+        Key::Escape => "Esc",
+        Key::Pause => "Pause",
+        Key::Home => "Home",
+        Key::End => "End",
+        Key::Left => "Left",
+        Key::Right => "Right",
+        Key::Up => "Up",
+        Key::Down => "Down",
+        Key::PageUp => "Page Up",
+        Key::PageDown => "Page Down",
+        Key::Print => "Print",
+        Key::Insert => "Insert",
+        Key::Delete => "Delete",
+        Key::Menu => "Menu",
+        Key::Help => "Help",
+        // The rest of fltk's range is keys without a character, like modifiers
+        _ if (Key::Button.bits()..=Key::Delete.bits()).contains(&bits) => return None,
+        _ => {
+            let c = char::from_u32(bits as u32).filter(|c| !c.is_control())?;
+            return Some(match c {
+                ' ' => "Space".into(),
+                c => c.to_uppercase().to_string(),
+            });
+        }
+    };
+    Some(name.into())
+}
+
+/// Sets the keyboard shortcut of `node` from the fltk shortcut `bits`, if there is one.
+pub(crate) fn set_shortcut(node: &mut Node, bits: i32) {
+    if let Some(text) = text(bits) {
+        node.set_keyboard_shortcut(text);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn with(mods: Shortcut, key: i32) -> Option<String> {
+        text(mods.bits() | key)
+    }
+
+    #[test]
+    fn modifiers() {
+        assert_eq!(with(Shortcut::Ctrl, 's' as i32).as_deref(), Some("Ctrl+S"));
+        assert_eq!(
+            with(Shortcut::Shift | Shortcut::Ctrl, 's' as i32).as_deref(),
+            Some("Ctrl+Shift+S")
+        );
+        let (alt, meta) = if cfg!(target_os = "macos") {
+            ("Option", "Cmd")
+        } else {
+            ("Alt", "Meta")
+        };
+        assert_eq!(
+            with(Shortcut::Alt, Key::Delete.bits()),
+            Some(format!("{alt}+Delete"))
+        );
+        assert_eq!(
+            with(Shortcut::Meta | Shortcut::Shift, 'q' as i32),
+            Some(format!("Shift+{meta}+Q"))
+        );
+        assert_eq!(with(Shortcut::Ctrl, '+' as i32).as_deref(), Some("Ctrl++"));
+        assert_eq!(text('x' as i32).as_deref(), Some("X"));
+    }
+
+    #[test]
+    fn no_key() {
+        assert_eq!(text(0), None);
+        // Modifiers alone aren't a shortcut
+        assert_eq!(with(Shortcut::Ctrl, 0), None);
+    }
+
+    #[test]
+    fn key_names() {
+        assert_eq!(key_name(Key::F1).as_deref(), Some("F1"));
+        assert_eq!(key_name(Key::F12).as_deref(), Some("F12"));
+        assert_eq!(key_name(Key::fn_key(5)).as_deref(), Some("F5"));
+        assert_eq!(key_name(Key::KPEnter).as_deref(), Some("Num Enter"));
+        assert_eq!(
+            key_name(Key::from_i32(Key::KP.bits() + '7' as i32)).as_deref(),
+            Some("Num 7")
+        );
+        assert_eq!(key_name(Key::PageDown).as_deref(), Some("Page Down"));
+        assert_eq!(key_name(Key::Escape).as_deref(), Some("Esc"));
+        assert_eq!(key_name(Key::from_char(' ')).as_deref(), Some("Space"));
+        assert_eq!(key_name(Key::from_char('a')).as_deref(), Some("A"));
+        // Modifier keys on their own have no name
+        assert_eq!(key_name(Key::ShiftL), None);
+    }
+}
@@ -0,0 +1,161 @@
+use accesskit::{Action, Node, NodeId, Role};
+use fltk::{enums::CallbackTrigger, group, prelude::*, widget};
+
+use crate::ids::{child_node_id, node_id};
+use crate::label::set_label;
+use crate::targets::Targets;
+
+/// Appends a tab list with the tab of each child of `tabs`, whose node is `tabs_id`, to
+/// `out` and returns its id. The tab of the shown child controls it, as that's the one
+/// panel in the tree; the tabs of the others only select theirs.
+pub(crate) fn tab_list(
+    tabs: &group::Tabs,
+    tabs_id: NodeId,
+    out: &mut Vec<(NodeId, Node)>,
+    targets: &mut Targets,
+) -> NodeId {
+    let shown = tabs.value().map(|v| v.as_widget_ptr());
+    let panels: Vec<widget::Widget> = (0..tabs.children()).filter_map(|i| tabs.child(i)).collect();
+    let mut list = Node::new(Role::TabList);
+    for (i, panel) in panels.iter().enumerate() {
+        let mut node = Node::new(Role::Tab);
+        set_label(&mut node, &panel.label());
+        let selected = shown == Some(panel.as_widget_ptr());
+        node.set_selected(selected);
+        node.add_action(Action::Click);
+        node.add_action(Action::Focus);
+        node.set_position_in_set(i + 1);
+        node.set_size_of_set(panels.len());
+        if selected {
+            node.push_controlled(node_id(panel));
+        }
+        let id = tab_id(tabs_id, panel);
+        targets.insert_tab(id, tabs, panel);
+        list.push_child(id);
+        out.push((id, node));
+    }
+    let list_id = list_id(tabs_id);
+    out.push((list_id, list));
+    list_id
+}
+
+/// Panel ids start at 1, so this one's free.
+fn list_id(tabs_id: NodeId) -> NodeId {
+    child_node_id(tabs_id, 0)
+}
+
+/// Keyed by the panel's own id rather than its address, which a later panel may get.
+fn tab_id(tabs_id: NodeId, panel: &widget::Widget) -> NodeId {
+    child_node_id(tabs_id, node_id(panel).0)
+}
+
+/// Makes `node`, the node of `w`, a tab panel labelled by its tab when `w` is a child
+/// of a `Tabs`.
+pub(crate) fn mark_panel(w: &widget::Widget, node: &mut Node) {
+    let Some(tabs) = w
+        .parent()
+        .and_then(|p| group::Tabs::from_dyn_widget_ptr(p.as_widget_ptr()))
+    else {
+        return;
+    };
+    node.set_role(Role::TabPanel);
+    node.push_labelled_by(tab_id(node_id(&tabs), w));
+}
+
+/// Shows `panel` like a click on its tab does, see `Fl_Tabs::maybe_do_callback`.
+pub(crate) fn select(tabs: &widget::Widget, panel: &widget::Widget, focus: bool) {
+    let Some(mut tabs) = group::Tabs::from_dyn_widget_ptr(tabs.as_widget_ptr()) else {
+        return;
+    };
+    let Some(panel) = group::Group::from_dyn_widget_ptr(panel.as_widget_ptr()) else {
+        return;
+    };
+    if focus {
+        let _ = tabs.take_focus();
+    }
+    let changed = tabs.value().map(|v| v.as_widget_ptr()) != Some(panel.as_widget_ptr());
+    if tabs.set_value(&panel).is_err() {
+        return;
+    }
+    if changed {
+        tabs.set_changed();
+    }
+    if changed || tabs.trigger().contains(CallbackTrigger::NotChanged) {
+        tabs.do_callback();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::cell::Cell;
+    use std::rc::Rc;
+
+    fn tabs() -> (group::Tabs, group::Group, group::Group) {
+        let tabs = group::Tabs::default();
+        let mut one = group::Group::default().with_label("One");
+        one.end();
+        let mut two = group::Group::default().with_label("Two");
+        two.end();
+        tabs.end();
+        (tabs, one, two)
+    }
+
+    #[test]
+    fn tabs_and_their_panels() {
+        let (mut tabs, one, two) = tabs();
+        tabs.set_value(&two).unwrap();
+        let tabs_id = node_id(&tabs);
+        let mut out = Vec::new();
+        let mut targets = Targets::default();
+        let list = tab_list(&tabs, tabs_id, &mut out, &mut targets);
+        let (id, node) = out.last().unwrap();
+        assert_eq!((*id, node.role()), (list, Role::TabList));
+        let ids = [
+            tab_id(tabs_id, &one.as_base_widget()),
+            tab_id(tabs_id, &two.as_base_widget()),
+        ];
+        assert_eq!(node.children(), ids);
+        let (_, first) = &out[0];
+        assert_eq!(first.label(), Some("One"));
+        assert!(!first.is_selected().unwrap());
+        assert!(first.controls().is_empty());
+        assert_eq!(
+            (first.position_in_set(), first.size_of_set()),
+            (Some(1), Some(2))
+        );
+        let (_, second) = &out[1];
+        assert!(second.is_selected().unwrap());
+        assert_eq!(second.controls(), [node_id(&two)]);
+        assert!(ids.iter().all(|id| targets.contains(*id)));
+        // The shown panel is labelled by its tab
+        let mut panel = Node::new(Role::Group);
+        mark_panel(&two.as_base_widget(), &mut panel);
+        assert_eq!(panel.role(), Role::TabPanel);
+        assert_eq!(panel.labelled_by(), [ids[1]]);
+    }
+
+    #[test]
+    fn selecting_runs_the_callback_on_change() {
+        let (mut tabs, one, two) = tabs();
+        tabs.set_value(&one).unwrap();
+        let calls = Rc::new(Cell::new(0));
+        tabs.set_callback({
+            let calls = calls.clone();
+            move |_| calls.set(calls.get() + 1)
+        });
+        let (t, panel) = (tabs.as_base_widget(), two.as_base_widget());
+        select(&t, &panel, false);
+        assert_eq!(
+            tabs.value().map(|v| v.as_widget_ptr()),
+            Some(two.as_widget_ptr())
+        );
+        assert_eq!(calls.get(), 1);
+        // Already shown
+        select(&t, &panel, false);
+        assert_eq!(calls.get(), 1);
+        tabs.set_trigger(CallbackTrigger::Changed | CallbackTrigger::NotChanged);
+        select(&t, &panel, false);
+        assert_eq!(calls.get(), 2);
+    }
+}
@@ -0,0 +1,184 @@
+use accesskit::{Action, NodeId};
+use fltk::{group, menu, prelude::*, widget};
+use std::collections::HashMap;
+
+/// What a node of the last tree update stands for.
+#[derive(Clone)]
+pub(crate) enum Target {
+    Widget(widget::Widget),
+    /// An item of `menu`'s item array, identified by its address.
+    MenuItem {
+        menu: widget::Widget,
+        item: usize,
+    },
+    /// A line of a browser, counting from 1.
+    BrowserLine {
+        browser: widget::Widget,
+        line: i32,
+    },
+    /// The tab of `panel`, a child of `tabs`.
+    Tab {
+        tabs: widget::Widget,
+        panel: widget::Widget,
+    },
+}
+
+/// Maps the ids of the last tree update to their targets, so that action requests are
+/// only ever applied to things we actually exposed and that still exist.
+#[derive(Default)]
+pub(crate) struct Targets {
+    map: HashMap<NodeId, Target>,
+}
+
+impl Targets {
+    pub(crate) fn insert_widget(&mut self, id: NodeId, w: &widget::Widget) {
+        self.map.insert(id, Target::Widget(w.clone()));
+    }
+
+    pub(crate) fn insert_menu_item<M: MenuExt>(
+        &mut self,
+        id: NodeId,
+        menu: &M,
+        item: &menu::MenuItem,
+    ) {
+        self.map.insert(
+            id,
+            Target::MenuItem {
+                menu: menu.as_base_widget(),
+                item: unsafe { item.as_ptr() } as usize,
+            },
+        );
+    }
+
+    pub(crate) fn insert_browser_line<W: WidgetExt>(&mut self, id: NodeId, browser: &W, line: i32) {
+        self.map.insert(
+            id,
+            Target::BrowserLine {
+                browser: browser.as_base_widget(),
+                line,
+            },
+        );
+    }
+
+    pub(crate) fn insert_tab(&mut self, id: NodeId, tabs: &group::Tabs, panel: &widget::Widget) {
+        self.map.insert(
+            id,
+            Target::Tab {
+                tabs: tabs.as_base_widget(),
+                panel: panel.clone(),
+            },
+        );
+    }
+
+    /// The widget `id` stands for, if it's a widget node that still exists.
+    pub(crate) fn widget(&self, id: NodeId) -> Option<widget::Widget> {
+        match self.map.get(&id) {
+            Some(Target::Widget(w)) if !w.was_deleted() => Some(w.clone()),
+            _ => None,
+        }
+    }
+
+    pub(crate) fn remove(&mut self, id: NodeId) {
+        self.map.remove(&id);
+    }
+
+    pub(crate) fn extend(&mut self, other: Targets) {
+        self.map.extend(other.map);
+    }
+
+    pub(crate) fn contains(&self, id: NodeId) -> bool {
+        self.map.contains_key(&id)
+    }
+
+    /// The live target of `id`. Unknown and stale ids are rejected, and logged in debug
+    /// builds.
+    pub(crate) fn resolve(&self, id: NodeId, action: Action) -> Option<Target> {
+        let Some(target) = self.map.get(&id) else {
+            reject(action, id, "unknown");
+            return None;
+        };
+        let live = match target {
+            Target::Widget(w) => !w.was_deleted(),
+            Target::MenuItem { menu, item } => {
+                !menu.was_deleted() && crate::menus::contains(menu, *item)
+            }
+            Target::BrowserLine { browser, line } => {
+                !browser.was_deleted() && *line <= crate::browsers::line_count(browser)
+            }
+            Target::Tab { tabs, panel } => {
+                !tabs.was_deleted()
+                    && !panel.was_deleted()
+                    && panel
+                        .parent()
+                        .is_some_and(|p| p.as_widget_ptr() == tabs.as_widget_ptr())
+            }
+        };
+        if !live {
+            reject(action, id, "deleted");
+            return None;
+        }
+        let owner = match target {
+            Target::Widget(w) => w,
+            Target::MenuItem { menu, .. } => menu,
+            Target::BrowserLine { browser, .. } => browser,
+            Target::Tab { tabs, .. } => tabs,
+        };
+        let enabled = match target {
+            Target::MenuItem { menu, item } => crate::menus::pickable(menu, *item),
+            _ => true,
+        };
+        if !owner.active_r() || !enabled {
+            reject(action, id, "disabled");
+            return None;
+        }
+        Some(target.clone())
+    }
+}
+
+/// Tells why a request did nothing, in debug builds only: clients often request actions
+/// on nodes that went away meanwhile, which isn't worth a line in a release build.
+fn reject(action: Action, id: NodeId, why: &str) {
+    if cfg!(debug_assertions) {
+        eprintln!("fltk-accesskit: ignoring {action:?} request for {why} node {id:?}");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use fltk::{browser, button, enums::Shortcut};
+
+    #[test]
+    fn unknown_and_stale_targets() {
+        let mut targets = Targets::default();
+        assert!(targets.resolve(NodeId(u64::MAX), Action::Click).is_none());
+
+        let mut b = button::Button::default();
+        let id = crate::node_id(&b);
+        targets.insert_widget(id, &b.as_base_widget());
+        assert!(targets.resolve(id, Action::Click).is_some());
+        b.deactivate();
+        assert!(targets.resolve(id, Action::Click).is_none());
+        b.activate();
+        button::Button::delete(b);
+        assert!(targets.resolve(id, Action::Click).is_none());
+
+        let mut menu = menu::MenuButton::default();
+        menu.add("One", Shortcut::None, menu::MenuFlag::Normal, |_| ());
+        let item = menu.find_item("One").unwrap();
+        let id = crate::child_node_id(crate::node_id(&menu), unsafe { item.as_ptr() } as u64);
+        targets.insert_menu_item(id, &menu, &item);
+        assert!(targets.resolve(id, Action::Click).is_some());
+        menu.remove(0);
+        assert!(targets.resolve(id, Action::Click).is_none());
+
+        let mut lines = browser::Browser::default();
+        lines.add("One");
+        lines.add("Two");
+        let id = crate::child_node_id(crate::node_id(&lines), 2);
+        targets.insert_browser_line(id, &lines, 2);
+        assert!(targets.resolve(id, Action::Click).is_some());
+        lines.remove(2);
+        assert!(targets.resolve(id, Action::Click).is_none());
+    }
+}